@@ -13,5 +13,13 @@ fn test_count_c(b: &mut Bencher) {
 
 #[bench]
 fn test_count_lua(b: &mut Bencher) {
-    b.iter(|| count("tests/data/lua-big.lua"))
+    b.iter(|| count("tests/data/lua.lua"))
+}
+
+#[bench]
+fn test_count_rust_strings(b: &mut Bencher) {
+    // lines full of strings and comment markers, the slow path of the line counter
+    let text = std::fs::read_to_string("tests/data/strings.rs").unwrap().repeat(2000);
+    let registry = LanguageRegistry::new();
+    b.iter(|| registry.try_count_text_lengths(Lang::Rust, &text, LONG_LINE))
 }
@@ -0,0 +1,477 @@
+// The built-in languages. This table is the only place that knows anything about them:
+// names, how to recognize their files, and their comment syntax. Entries are in the same
+// order as the variants of `Lang`, so lookups by `Lang` can binary search.
+//
+// NOTE(cgag): while we lifted most of this from tokei, we support a few
+// more extensions in some places, can't just assume it's the same.
+
+use Lang;
+use Lang::*;
+use Literate;
+use Quote;
+
+pub struct Builtin {
+    pub lang: Lang,
+    pub name: &'static str,
+    /// Lowercase, without the dot.
+    pub extensions: &'static [&'static str],
+    /// File names that identify the language regardless of extension, matched case
+    /// insensitively.
+    pub filenames: &'static [&'static str],
+    /// Patterns for file names, see `LanguageRegistry::by_filename`.
+    pub filename_patterns: &'static [&'static str],
+    /// Interpreter names, see `shebang_interpreter`.
+    pub shebangs: &'static [&'static str],
+    pub comments: Comments,
+    pub strings: &'static [Quote<'static>],
+    /// Comment starts that mark documentation, see `Syntax::doc_comments`.
+    pub doc_comments: &'static [&'static str],
+    pub literate: Option<Literate>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Comments {
+    pub line: &'static [&'static str],
+    pub block: &'static [(&'static str, &'static str)],
+    /// Whether block comments nest: in C `/* /* */` is a whole comment, in Haskell
+    /// `{- {- -}` is still waiting for another `-}`.
+    pub nested: bool,
+    /// Whether a line comment after code has to follow whitespace, see `Syntax`.
+    pub spaced: bool,
+}
+
+const fn comments(line: &'static [&'static str],
+                  block: &'static [(&'static str, &'static str)]) -> Comments {
+    Comments { line, block, nested: false, spaced: false }
+}
+
+impl Comments {
+    const fn nested(self) -> Comments {
+        Comments { nested: true, ..self }
+    }
+
+    const fn spaced(self) -> Comments {
+        Comments { spaced: true, ..self }
+    }
+}
+
+const fn lang(lang: Lang,
+              name: &'static str,
+              extensions: &'static [&'static str],
+              comments: Comments) -> Builtin {
+    Builtin { lang, name, extensions, filenames: &[], filename_patterns: &[], shebangs: &[], comments,
+              strings: &[], doc_comments: &[], literate: None }
+}
+
+impl Builtin {
+    const fn filenames(self, filenames: &'static [&'static str]) -> Builtin {
+        Builtin { filenames, ..self }
+    }
+
+    const fn filename_patterns(self, filename_patterns: &'static [&'static str]) -> Builtin {
+        Builtin { filename_patterns, ..self }
+    }
+
+    const fn shebangs(self, shebangs: &'static [&'static str]) -> Builtin {
+        Builtin { shebangs, ..self }
+    }
+
+    const fn strings(self, strings: &'static [Quote<'static>]) -> Builtin {
+        Builtin { strings, ..self }
+    }
+
+    const fn doc_comments(self, doc_comments: &'static [&'static str]) -> Builtin {
+        Builtin { doc_comments, ..self }
+    }
+
+    const fn literate(self, literate: Literate) -> Builtin {
+        Builtin { literate: Some(literate), ..self }
+    }
+}
+
+const C_STYLE: Comments       = comments(&["//"], &[("/*", "*/")]);
+const HASKELL_STYLE: Comments = comments(&["--"], &[("{-", "-}")]).nested();
+const HTML_STYLE: Comments    = comments(&[], &[("<!--", "-->")]);
+const ML_STYLE: Comments      = comments(&[], &[("(*", "*)")]).nested();
+const NO_COMMENTS: Comments   = comments(&[], &[]);
+const PROLOG_STYLE: Comments  = comments(&["%"], &[("/*", "*/")]);
+const SH_STYLE: Comments      = comments(&["#"], &[]);
+// `$#`, `${#list}` and `${path##*/}` aren't comments, a # has to start a word
+const SHELL_STYLE: Comments   = SH_STYLE.spaced();
+
+// A line comment after code is only recognized in languages whose strings are listed,
+// since otherwise the marker could be inside one. Languages without them only count
+// comments that start a line. Shell-like languages and config files get single line
+// strings: their strings can span lines, but apostrophes in heredocs and unquoted text
+// would make a multiline string a guess.
+
+/// A string with backslash escapes that can span lines.
+const fn quote(start: &'static str, end: &'static str) -> Quote<'static> {
+    Quote { start, end, escape: Some("\\"), multiline: true }
+}
+
+impl Quote<'static> {
+    const fn raw(self) -> Quote<'static> {
+        Quote { escape: None, ..self }
+    }
+
+    const fn single_line(self) -> Quote<'static> {
+        Quote { multiline: false, ..self }
+    }
+}
+
+const C_STRINGS: &[Quote]      = &[quote("\"", "\"").single_line(), quote("'", "'").single_line()];
+const CPP_STRINGS: &[Quote]    = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   // TODO(cgag): custom delimiters, R"x(...)x"
+                                   quote("R\"(", ")\"").raw()];
+const CS_STRINGS: &[Quote]     = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   quote("@\"", "\"").raw()];
+const D_STRINGS: &[Quote]      = &[quote("\"", "\""), quote("'", "'").single_line(),
+                                   quote("`", "`").raw()];
+const DQ_STRINGS: &[Quote]     = &[quote("\"", "\"")];
+const DQ_SINGLE_LINE: &[Quote] = &[quote("\"", "\"").single_line()];
+const GO_STRINGS: &[Quote]     = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   quote("`", "`").raw()];
+const JS_STRINGS: &[Quote]     = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   quote("`", "`")];
+const JVM_STRINGS: &[Quote]    = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   quote("\"\"\"", "\"\"\"")];
+const LUA_STRINGS: &[Quote]    = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   quote("[[", "]]").raw()];
+const PASCAL_STRINGS: &[Quote] = &[quote("'", "'").raw().single_line()];
+const PS_STRINGS: &[Quote]     = &[Quote { escape: Some("`"), ..quote("\"", "\"") },
+                                   quote("'", "'").raw()];
+const PY_STRINGS: &[Quote]     = &[quote("\"", "\"").single_line(), quote("'", "'").single_line(),
+                                   quote("\"\"\"", "\"\"\""), quote("'''", "'''")];
+const RUST_STRINGS: &[Quote]   = &[quote("\"", "\""), quote("'", "'").single_line(),
+                                   quote("r\"", "\"").raw(), quote("r#\"", "\"#").raw(),
+                                   quote("r##\"", "\"##").raw(), quote("r###\"", "\"###").raw()];
+const SCRIPT_STRINGS: &[Quote] = &[quote("\"", "\""), quote("'", "'")];
+// '' in YAML and "" in Ada are an end and a start, so raw strings get them right
+const SHELL_STRINGS: &[Quote]  = &[quote("\"", "\"").single_line(), quote("'", "'").raw().single_line()];
+const SQL_STRINGS: &[Quote]    = &[quote("'", "'").raw()];
+const TOML_STRINGS: &[Quote]   = &[quote("\"", "\"").single_line(), quote("'", "'").raw().single_line(),
+                                   quote("\"\"\"", "\"\"\""), quote("'''", "'''").raw()];
+
+const DOXYGEN: &[&str] = &["///", "//!", "/**", "/*!"];
+const HADDOCK: &[&str] = &["-- |", "-- ^", "-- $", "{-|", "{- |"];
+const JAVADOC: &[&str] = &["/**"];
+
+/// The table entry for a built-in language, None for custom languages and `Unrecognized`.
+pub fn builtin(lang: Lang) -> Option<&'static Builtin> {
+    BUILTIN.binary_search_by(|b| b.lang.cmp(&lang)).ok().map(|i| &BUILTIN[i])
+}
+
+pub static BUILTIN: &[Builtin] = &[
+    lang(ActionScript, "ActionScript", &["as"], C_STYLE)
+        .strings(JS_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Ada, "Ada", &["ada", "adb", "ads", "pad"], comments(&["--"], &[]))
+        // no '...', a tick is an attribute as often as a character literal: X'Length
+        .strings(&[quote("\"", "\"").raw().single_line()]),
+    lang(Agda, "Agda", &["agda"], HASKELL_STYLE)
+        .strings(DQ_SINGLE_LINE),
+    lang(AmbientTalk, "AmbientTalk", &["at"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Asp, "ASP", &["asa", "asp"], comments(&["'", "REM"], &[])),
+    lang(AspNet, "ASP.NET", &["asax", "ascx", "asmx", "aspx", "master", "sitemap", "webinfo"],
+        comments(&[], &[("<!--", "-->"), ("<%--", "-->")])),
+    // TODO(cgag): Well, some architectures use ;, @, |, etc.  Figure out something
+    // better?
+    lang(Assembly, "Assembly", &["s", "asm"], comments(&["#"], &[("/*", "*/")]))
+        .strings(DQ_SINGLE_LINE),
+    lang(Autoconf, "Autoconf", &["in"], comments(&["#", "dnl"], &[])),
+    lang(Awk, "Awk", &["awk"], SH_STYLE)
+        .shebangs(&["awk", "gawk", "mawk", "nawk"])
+        .strings(DQ_SINGLE_LINE),
+    lang(Batch, "Batch", &["bat", "btm", "cmd"], comments(&["REM"], &[])),
+    lang(BourneShell, "Bourne Shell", &["sh"], SHELL_STYLE)
+        .shebangs(&["bash", "sh", "dash", "ash", "ksh"])
+        .strings(SHELL_STRINGS),
+    lang(C, "C", &["c", "ec", "pgc"], C_STYLE)
+        .strings(C_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(CCppHeader, "C/C++ Header", &["h", "hh", "hpp", "hxx"], C_STYLE)
+        .strings(CPP_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(CMake, "CMake", &["cmake"], comments(&["#"], &[("#[[", "]]")]))
+        .filenames(&["CMakeLists.txt"])
+        .strings(DQ_STRINGS),
+    lang(CSharp, "C#", &["cs"], C_STYLE)
+        .strings(CS_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(CShell, "C Shell", &["csh"], SHELL_STYLE)
+        .shebangs(&["csh"])
+        .strings(SHELL_STRINGS),
+    lang(Clojure, "Clojure", &["clj"], comments(&[";"], &[]))
+        .strings(DQ_STRINGS),
+    lang(ClojureScript, "ClojureScript", &["cljs"], comments(&[";"], &[]))
+        .strings(DQ_STRINGS),
+    lang(ClojureC, "ClojureC", &["cljc"], comments(&[";"], &[]))
+        .strings(DQ_STRINGS),
+    lang(CoffeeScript, "CoffeeScript", &["coffee"], comments(&["#"], &[("###", "###")]))
+        .strings(PY_STRINGS),
+    lang(ColdFusion, "ColdFusion", &[], comments(&[], &[("<!---", "--->")])),
+    lang(ColdFusionScript, "ColdFusionScript", &["cfc"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Coq, "Coq", &["v"], ML_STYLE)
+        .strings(DQ_STRINGS),
+    lang(Cpp, "C++", &["cc", "cpp", "cxx", "c++", "pcc"], C_STYLE)
+        .strings(CPP_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(Crystal, "Crystal", &["cr"], SH_STYLE)
+        .strings(C_STRINGS),
+    lang(Css, "CSS", &["css", "pcss", "sss", "postcss"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(CUDA, "CUDA", &["cu"], C_STYLE)
+        .strings(CPP_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(CUDAHeader, "CUDA Header", &["cuh"], C_STYLE)
+        .strings(CPP_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(D, "D", &["d"], C_STYLE)
+        .strings(D_STRINGS)
+        .doc_comments(&["///", "/**", "/++"]),
+    lang(Dart, "Dart", &["dart"], C_STYLE.nested())
+        .strings(JVM_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(Dhall, "Dhall", &["dhall"], HASKELL_STYLE)
+        .strings(DQ_STRINGS),
+    lang(DeviceTree, "DeviceTree", &["dts", "dtsi"], C_STYLE)
+        .strings(DQ_SINGLE_LINE),
+    lang(Docker, "Docker", &["docker"], SHELL_STYLE)
+        .filenames(&["Dockerfile"])
+        .strings(SHELL_STRINGS),
+    // @doc and friends are strings, but they're documentation, not code
+    lang(Elixir, "Elixir", &["ex", "exs"],
+        comments(&["#", "@doc \"", "@moduledoc \"", "@typedoc \""],
+                 &[("@doc \"\"\"", "\"\"\""),
+                   ("@moduledoc \"\"\"", "\"\"\""),
+                   ("@typedoc \"\"\"", "\"\"\"")]))
+        .doc_comments(&["@doc", "@moduledoc", "@typedoc"]),
+    lang(Elm, "Elm", &["elm"], HASKELL_STYLE)
+        .strings(DQ_SINGLE_LINE)
+        .doc_comments(HADDOCK),
+    lang(Erlang, "Erlang", &["erl", "hrl"], comments(&["%"], &[]))
+        .strings(C_STRINGS),
+    lang(Fish, "Fish", &["fish"], SHELL_STYLE)
+        .shebangs(&["fish"])
+        .strings(SHELL_STRINGS),
+    lang(Forth, "Forth",
+        &["4th", "forth", "fr", "frt", "fth", "f83", "fb", "fpm", "e4", "rx", "ft"],
+        comments(&["\\"], &[("(", ")")])),
+    lang(FortranLegacy, "FORTRAN Legacy", &["f", "for", "ftn", "f77", "pfo"],
+        comments(&["c", "C", "!", "*"], &[])),
+    lang(FortranModern, "FORTRAN Modern", &["f03", "f08", "f90", "f95"], comments(&["!"], &[]))
+        .strings(&[quote("\"", "\"").raw().single_line(), quote("'", "'").raw().single_line()]),
+    lang(FSharp, "F#", &["fs", "fsx"], comments(&["//"], &[("(*", "*)")]).nested())
+        .strings(JVM_STRINGS)
+        .doc_comments(&["///", "(**"]),
+    lang(Gherkin, "Gherkin", &["feature"], SH_STYLE),
+    // no strings in GLSL, but listing some lets comments follow code
+    lang(Glsl, "GLSL", &["vert", "tesc", "tese", "geom", "frag", "comp"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Go, "Go", &["go"], C_STYLE)
+        .strings(GO_STRINGS),
+    lang(Groovy, "Groovy", &["groovy"], C_STYLE)
+        .strings(JVM_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Handlebars, "Handlebars", &["hbs", "handlebars"],
+        comments(&[], &[("<!--", "-->"), ("{{!", "}}")])),
+    lang(Haskell, "Haskell", &["hs"], HASKELL_STYLE)
+        .shebangs(&["stack", "runhaskell"])
+        .strings(DQ_SINGLE_LINE)
+        .doc_comments(HADDOCK),
+    lang(Hex, "Hex", &["hex"], NO_COMMENTS),
+    lang(Html, "HTML", &["html"], HTML_STYLE),
+    lang(INI, "INI", &["ini"], comments(&[";"], &[]).spaced())
+        .strings(SHELL_STRINGS),
+    lang(Idris, "Idris", &["idr"], HASKELL_STYLE)
+        .strings(DQ_SINGLE_LINE),
+    lang(IntelHex, "Intel Hex", &["ihex"], NO_COMMENTS),
+    // Is that angle bracket utf8?  What's going to happen with that?
+    lang(Isabelle, "Isabelle", &["thy"],
+        comments(&["--"], &[("{*", "*}"), ("(*", "*)"), ("‹", "›"),
+                            ("\\<open>", "\\<close>")]).nested()),
+    lang(Jai, "Jai", &["jai"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Java, "Java", &["java"], C_STYLE)
+        .strings(JVM_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(JavaScript, "JavaScript", &["js", "mjs"], C_STYLE)
+        .shebangs(&["node", "nodejs"])
+        .strings(JS_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Json, "JSON", &["json"], NO_COMMENTS),
+    lang(Jsx, "Jsx", &["jsx"], C_STYLE)
+        .strings(JS_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Julia, "Julia", &["jl"], comments(&["#"], &[("#=", "=#")]).nested())
+        .shebangs(&["julia"])
+        .strings(DQ_STRINGS),
+    lang(Kotlin, "Kotlin", &["kt", "kts"], C_STYLE.nested())
+        .strings(JVM_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Less, "Less", &["less"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(LinkerScript, "LinkerScript", &["lds"], C_STYLE)
+        .strings(DQ_SINGLE_LINE),
+    lang(Lean, "Lean", &["lean", "hlean"], comments(&["--"], &[("/-", "-/")]).nested())
+        .strings(DQ_STRINGS),
+    lang(Lisp, "Lisp", &["el", "lisp", "lsp", "scm", "ss", "rkt"],
+        comments(&[";"], &[("#|", "|#")]).nested())
+        .strings(DQ_STRINGS),
+    lang(LiterateAgda, "Literate Agda", &["lagda"], NO_COMMENTS)
+        .literate(Literate::Latex),
+    lang(LiterateCoffeeScript, "Literate Coffee", &["litcoffee"], NO_COMMENTS)
+        .literate(Literate::Indented),
+    lang(LiterateHaskell, "Literate Haskell", &["lhs"], NO_COMMENTS)
+        .literate(Literate::Bird),
+    lang(LiterateIdris, "Literate Idris", &["lidr"], NO_COMMENTS)
+        .literate(Literate::Bird),
+    lang(Lua, "Lua", &["lua"], comments(&["--"], &[("--[[", "]]")]))
+        .shebangs(&["lua"])
+        .strings(LUA_STRINGS)
+        .doc_comments(&["---"]),
+    lang(Make, "Make", &[], SHELL_STYLE)
+        .strings(SHELL_STRINGS),
+    // recipes are shell scripts
+    lang(Makefile, "Makefile", &["makefile", "mk"], SHELL_STYLE)
+        .filenames(&["Makefile", "GNUmakefile"])
+        // Makefile.am, makefile.linux, BSDmakefile...
+        .filename_patterns(&["*makefile*"])
+        .shebangs(&["make"])
+        .strings(SHELL_STRINGS),
+    lang(Markdown, "Markdown", &["markdown", "md"], NO_COMMENTS),
+    // MATLAB and Octave share .m with Objective-C, see heuristics.rs
+    lang(Matlab, "MATLAB", &[], comments(&["%"], &[("%{", "%}")]).nested()),
+    lang(Mustache, "Mustache", &["mustache"], comments(&[], &[("{{!", "}}")])),
+    lang(Nim, "Nim", &["nim"], SH_STYLE)
+        .strings(PY_STRINGS)
+        .doc_comments(&["##"]),
+    lang(Nix, "Nix", &["nix"], comments(&["#"], &[("/*", "*/")]))
+        .strings(DQ_STRINGS),
+    lang(OCaml, "OCaml", &["ml", "mli"], ML_STYLE)
+        .strings(DQ_STRINGS)
+        .doc_comments(&["(**"]),
+    lang(ObjectiveC, "Objective-C", &["m"], C_STYLE)
+        .strings(C_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(ObjectiveCpp, "Objective-C++", &["mm"], C_STYLE)
+        .strings(CPP_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(Octave, "Octave", &[], comments(&["%", "#"], &[("%{", "%}"), ("#{", "#}")]).nested()),
+    lang(OpenCl, "OpenCL", &["cl"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Oz, "Oz", &["oz"], PROLOG_STYLE)
+        .strings(C_STRINGS),
+    lang(Pascal, "Pascal", &["pas"], comments(&["//", "(*"], &[("{", "}")]))
+        .strings(PASCAL_STRINGS),
+    // which one is right? = or =pod?
+    lang(Perl, "Perl", &["pl", "pm"], comments(&["#"], &[("=pod", "=cut")]))
+        .shebangs(&["perl", "perl6"])
+        .strings(SCRIPT_STRINGS)
+        .doc_comments(&["=pod"]),
+    lang(Php, "PHP", &["php"], comments(&["#", "//"], &[("/*", "*/")]))
+        .shebangs(&["php"])
+        .strings(SCRIPT_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Polly, "Polly", &["polly"], HTML_STYLE),
+    lang(PowerShell, "PowerShell", &["ps1", "psd1", "psm1"], comments(&["#"], &[("<#", "#>")]))
+        .strings(PS_STRINGS),
+    lang(Prolog, "Prolog", &["p", "pro"], PROLOG_STYLE)
+        .strings(C_STRINGS),
+    lang(Protobuf, "Protobuf", &["proto"], comments(&["//"], &[]))
+        .strings(C_STRINGS),
+    lang(Puppet, "Puppet", &["pp"], SH_STYLE)
+        .strings(C_STRINGS),
+    lang(PureScript, "PureScript", &["purs"], HASKELL_STYLE)
+        .strings(DQ_SINGLE_LINE)
+        .doc_comments(HADDOCK),
+    lang(Pyret, "Pyret", &["arr"], comments(&["#"], &[("#|", "|#")]).nested())
+        .strings(C_STRINGS),
+    lang(Python, "Python", &["py"], comments(&["#"], &[("'''", "'''"), ("\"\"\"", "\"\"\"")]))
+        .shebangs(&["python", "python2", "python3", "pypy", "pypy3"])
+        .strings(PY_STRINGS)
+        .doc_comments(&["\"\"\"", "'''"]),
+    lang(Qcl, "Qcl", &["qcl"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Qml, "Qml", &["qml"], C_STYLE)
+        .strings(JS_STRINGS),
+    lang(QMake, "QMake", &["pri", "prf"], SH_STYLE)
+        .strings(DQ_SINGLE_LINE),
+    lang(R, "R", &["r"], SH_STYLE)
+        .shebangs(&["Rscript"])
+        .strings(C_STRINGS),
+    lang(Razor, "Razor", &["cshtml"], comments(&[], &[("<!--", "-->"), ("@*", "*@")])),
+    lang(Reason, "Reason", &["re", "rei"], C_STYLE)
+        .strings(DQ_STRINGS),
+    lang(Ron, "RON", &["ron"], C_STYLE.nested())
+        .strings(RUST_STRINGS),
+    lang(ReStructuredText, "reStructuredText", &["rst"], NO_COMMENTS),
+    lang(Ruby, "Ruby", &["rake", "rb"], comments(&["#"], &[("=begin", "=end")]))
+        .shebangs(&["ruby"])
+        .strings(SCRIPT_STRINGS),
+    lang(RubyHtml, "RubyHtml", &["rhtml", "erb"], HTML_STYLE),
+    lang(Rust, "Rust", &["rs"], C_STYLE.nested())
+        .strings(RUST_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(SaltStack, "SaltStack", &["sls"], SHELL_STYLE)
+        .strings(SHELL_STRINGS),
+    lang(Sass, "Sass", &["sass", "scss"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Scala, "Scala", &["sc", "scala"], C_STYLE.nested())
+        .strings(JVM_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Sml, "SML", &["sml"], ML_STYLE)
+        .strings(DQ_STRINGS),
+    lang(Solidity, "Solidity", &["sol"], C_STYLE)
+        .strings(C_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(Sql, "SQL", &["sql"], comments(&["--"], &[("/*", "*/")]))
+        .strings(SQL_STRINGS),
+    lang(Stylus, "Stylus", &["styl"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Svelte, "Svelte", &["svelte"], comments(&["//"], &[("/*", "*/"), ("<!--", "-->")])),
+    lang(Swift, "Swift", &["swift"], C_STYLE.nested())
+        .strings(JVM_STRINGS)
+        .doc_comments(DOXYGEN),
+    lang(Tcl, "Tcl", &["tcl"], SH_STYLE)
+        .shebangs(&["tclsh", "wish"]),
+    lang(Terraform, "Terraform", &["tf"], comments(&["#"], &[("/*", "*/")]))
+        .strings(DQ_SINGLE_LINE),
+    lang(Tex, "TeX", &["tex", "sty"], comments(&["%"], &[])),
+    lang(Text, "Plain Text", &["text", "txt"], NO_COMMENTS),
+    lang(Toml, "Toml", &["toml"], SH_STYLE)
+        .strings(TOML_STRINGS),
+    lang(TypeScript, "TypeScript", &["ts"], C_STYLE)
+        .shebangs(&["deno", "ts-node"])
+        .strings(JS_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(Tsx, "Typescript JSX", &["tsx"], C_STYLE)
+        .strings(JS_STRINGS)
+        .doc_comments(JAVADOC),
+    lang(UnrealScript, "UnrealScript", &["uc", "uci", "upkg"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Verilog, "Verilog", &["vh"], C_STYLE)
+        .strings(DQ_SINGLE_LINE),
+    lang(VimScript, "VimL", &["vim"], comments(&["\""], &[])),
+    lang(Vue, "Vue", &["vue"], comments(&["//"], &[("/*", "*/"), ("<!--", "-->")])),
+    lang(Wolfram, "Wolfram", &["nb", "wl"], ML_STYLE)
+        .strings(DQ_STRINGS),
+    lang(XML, "XML", &["xml"], HTML_STYLE),
+    // TODO(cgag): not 100% sure that yacc belongs here.
+    lang(Yacc, "Yacc", &["y"], C_STYLE)
+        .strings(C_STRINGS),
+    lang(Yaml, "YAML", &["yaml", "yml"], SHELL_STYLE)
+        .strings(SHELL_STRINGS),
+    // TODO(cgag): \\ multiline strings run to the end of the line, so Zig can't list its strings
+    lang(Zig, "Zig", &["zig"], comments(&["//"], &[]))
+        .doc_comments(&["///", "//!"]),
+    lang(Zsh, "Z Shell", &["zsh"], SHELL_STYLE)
+        .shebangs(&["zsh"])
+        .strings(SHELL_STRINGS),
+    lang(Haxe, "Haxe", &["hx"], C_STYLE)
+        .strings(C_STRINGS)
+        .doc_comments(JAVADOC),
+];
@@ -0,0 +1,320 @@
+// Files that mix languages: HTML with <script> and <style> blocks, Vue and Svelte single
+// file components, Razor and ERB templates, and the code blocks in Markdown and
+// reStructuredText documents. Their lines are split into regions, and each region is
+// counted as its own language.
+//
+// Regions are whole lines. The lines with the tags that open and close a region belong to
+// the host file's language, so `<script>x()</script>` on one line is all HTML.
+
+use super::*;
+
+/// Whether `lang` is a language whose files can embed other languages.
+pub fn is_host(lang: Lang) -> bool {
+    matches!(lang, Html | Vue | Svelte | Razor | RubyHtml | Markdown | ReStructuredText)
+}
+
+/// The lines of a host file, sorted by language.
+pub struct Regions {
+    host_lang: Lang,
+    /// Lines in the host's language, like HTML tags or Markdown code fences.
+    pub host: String,
+    /// Documentation lines, like the paragraphs of a Markdown file.
+    pub prose: String,
+    /// One text per embedded block, in file order. Blocks are counted one at a time, so an
+    /// unclosed comment in one `<script>` doesn't run on into the next.
+    pub embedded: Vec<(Lang, String)>,
+    /// Whether the last line pushed was embedded, so the next one in the same language is
+    /// part of the same block.
+    in_block: bool,
+}
+
+impl Regions {
+    fn push(&mut self, lang: Lang, line: &str) {
+        let text = if lang == self.host_lang {
+            self.in_block = false;
+            &mut self.host
+        } else {
+            if !(self.in_block && self.embedded.last().is_some_and(|&(l, _)| l == lang)) {
+                self.embedded.push((lang, String::new()));
+            }
+            self.in_block = true;
+            &mut self.embedded.last_mut().expect("just pushed").1
+        };
+        text.push_str(line);
+        text.push('\n');
+    }
+
+    fn push_prose(&mut self, line: &str) {
+        self.in_block = false;
+        self.prose.push_str(line);
+        self.prose.push('\n');
+    }
+
+    /// Adds a line of an ERB tag as Ruby, without its delimiters so `<%# note %>` is a Ruby
+    /// comment. A line that's only `<%` or `%>` stays with the host.
+    fn push_erb(&mut self, line: &str) {
+        let trimmed = line.trim();
+        let trimmed = trimmed.strip_prefix("<%").map_or(trimmed, |rest| rest.trim_start_matches(&['=', '-'][..]));
+        let trimmed = trimmed.strip_suffix("%>").map_or(trimmed, |rest| rest.trim_end_matches('-'));
+        if trimmed.trim().is_empty() {
+            let host = self.host_lang;
+            self.push(host, line);
+        } else {
+            self.push(Ruby, trimmed);
+        }
+    }
+}
+
+enum Block {
+    /// Inside <script> or <style>, until the closing tag.
+    Tag { close: &'static str, lang: Lang },
+    /// Inside a Razor @{ } block, until the braces balance.
+    Razor { depth: i32 },
+    /// Inside an ERB <% %> tag that spans lines.
+    Erb,
+    /// Inside a Markdown code block fenced with at least `len` backticks or tildes.
+    Fence { fence: char, len: usize, lang: Lang },
+    /// Inside a reStructuredText code-block directive, until a line that isn't indented
+    /// past `indent`. Blank lines are held back in `blanks` until we know whether the
+    /// block goes on after them.
+    Directive { indent: usize, lang: Lang, options: bool, blanks: usize },
+}
+
+/// Splits a host file into its languages. A code block in a language we don't know, or
+/// without one, stays with the host.
+pub fn split(languages: &LanguageRegistry, host: Lang, text: &str) -> Regions {
+    let mut regions = Regions {
+        host_lang: host,
+        host: String::new(),
+        prose: String::new(),
+        embedded: vec![],
+        in_block: false,
+    };
+    let is_doc = host == Markdown || host == ReStructuredText;
+
+    let mut block: Option<Block> = None;
+    for line in text_lines(text) {
+        let trimmed = line.trim();
+
+        // an unindented line ends a code-block directive, and is read like any other line
+        if let Some(Block::Directive { indent, blanks, .. }) = block {
+            if !trimmed.is_empty() && indentation(line) <= indent {
+                for _ in 0..blanks {
+                    regions.push_prose("");
+                }
+                block = None;
+            }
+        }
+
+        block = match block {
+            Some(Block::Tag { close, lang }) => {
+                if contains_tag(trimmed, close) {
+                    regions.push(host, line);
+                    None
+                } else {
+                    regions.push(lang, line);
+                    Some(Block::Tag { close, lang })
+                }
+            }
+            Some(Block::Razor { depth }) => {
+                let depth = depth + brace_depth(line);
+                if depth <= 0 {
+                    regions.push(host, line);
+                    None
+                } else {
+                    regions.push(CSharp, line);
+                    Some(Block::Razor { depth })
+                }
+            }
+            Some(Block::Erb) => {
+                regions.push_erb(line);
+                if trimmed.contains("%>") { None } else { Some(Block::Erb) }
+            }
+            Some(Block::Fence { fence, len, lang }) => {
+                if trimmed.len() >= len && trimmed.chars().all(|c| c == fence) {
+                    regions.push(host, line);
+                    None
+                } else {
+                    regions.push(lang, line);
+                    Some(Block::Fence { fence, len, lang })
+                }
+            }
+            Some(Block::Directive { indent, lang, options, blanks }) => {
+                if trimmed.is_empty() {
+                    Some(Block::Directive { indent, lang, options, blanks: blanks + 1 })
+                } else if options && trimmed.starts_with(':') {
+                    // :linenos: and friends, right after the directive
+                    regions.push(host, line);
+                    Some(Block::Directive { indent, lang, options, blanks })
+                } else {
+                    for _ in 0..blanks {
+                        regions.push(lang, "");
+                    }
+                    regions.push(lang, line);
+                    Some(Block::Directive { indent, lang, options: false, blanks: 0 })
+                }
+            }
+            None => {
+                if let Some((close, lang)) = open_tag(languages, trimmed) {
+                    regions.push(host, line);
+                    if contains_tag(trimmed, close) { None } else { Some(Block::Tag { close, lang }) }
+                } else if host == Razor && is_razor_block(trimmed) {
+                    regions.push(host, line);
+                    let depth = brace_depth(line);
+                    if depth > 0 { Some(Block::Razor { depth }) } else { None }
+                } else if host == RubyHtml && trimmed.starts_with("<%") {
+                    regions.push_erb(line);
+                    if trimmed.contains("%>") { None } else { Some(Block::Erb) }
+                } else if let (Markdown, Some((fence, len, info))) = (host, open_fence(trimmed)) {
+                    regions.push(host, line);
+                    let lang = named_language(languages, info).unwrap_or(host);
+                    Some(Block::Fence { fence, len, lang })
+                } else if let (ReStructuredText, Some(name)) = (host, code_directive(trimmed)) {
+                    regions.push(host, line);
+                    let lang = named_language(languages, name).unwrap_or(host);
+                    Some(Block::Directive { indent: indentation(line), lang, options: true, blanks: 0 })
+                } else if is_doc {
+                    regions.push_prose(line);
+                    None
+                } else {
+                    regions.push(host, line);
+                    None
+                }
+            }
+        };
+    }
+
+    if let Some(Block::Directive { blanks, .. }) = block {
+        for _ in 0..blanks {
+            regions.push_prose("");
+        }
+    }
+    regions
+}
+
+/// The language a code block or tag names, like "ts", "Rust" or "bash": by extension,
+/// then by name, then by interpreter.
+fn named_language(languages: &LanguageRegistry, name: &str) -> Option<Lang> {
+    languages.lookup(name).map(|language| language.lang)
+}
+
+/// The closing tag and language of a <script> or <style> tag starting `line`. None for
+/// other lines, and for scripts that aren't code, like templates.
+fn open_tag(languages: &LanguageRegistry, line: &str) -> Option<(&'static str, Lang)> {
+    let (close, default) = if starts_with_tag(line, "<script") {
+        ("</script>", JavaScript)
+    } else if starts_with_tag(line, "<style") {
+        ("</style>", Css)
+    } else {
+        return None;
+    };
+    // only the lines that open a tag are lowercased, for the attributes
+    let line = &line.to_lowercase();
+
+    // lang="ts", lang="scss"... Vue and Svelte use extensions, but names work too.
+    if let Some(lang) = attribute(line, "lang") {
+        return named_language(languages, lang).map(|lang| (close, lang));
+    }
+    match attribute(line, "type") {
+        None | Some("text/javascript") | Some("application/javascript") | Some("module") =>
+            Some((close, default)),
+        Some("text/typescript") | Some("application/typescript") => Some((close, TypeScript)),
+        Some("application/json") | Some("application/ld+json") | Some("importmap") => Some((close, Json)),
+        Some("text/css") if default == Css => Some((close, Css)),
+        // text/x-template and friends are markup
+        Some(_) => None,
+    }
+}
+
+/// Whether `line` starts with `tag`, in any case.
+fn starts_with_tag(line: &str, tag: &str) -> bool {
+    line.as_bytes().get(..tag.len()).is_some_and(|start| start.eq_ignore_ascii_case(tag.as_bytes()))
+}
+
+/// Whether `line` has `tag` anywhere in it, in any case.
+fn contains_tag(line: &str, tag: &str) -> bool {
+    memchr::memchr_iter(b'<', line.as_bytes()).any(|i| starts_with_tag(&line[i..], tag))
+}
+
+/// The value of `name="value"` or `name='value'` in a tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let end = tag.find('>').unwrap_or(tag.len());
+    let tag = &tag[..end];
+    let mut from = 0;
+    while let Some(i) = tag[from..].find(name) {
+        let start = from + i;
+        from = start + name.len();
+        // lang=, not xml:lang= or a value containing "lang"
+        if !tag[..start].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let rest = tag[from..].trim_start().strip_prefix('=')?.trim_start();
+        let quote = rest.chars().next()?;
+        if quote == '"' || quote == '\'' {
+            return rest[1..].split(quote).next();
+        }
+        return rest.split(|c: char| c.is_whitespace() || c == '>').next();
+    }
+    None
+}
+
+/// `@{`, `@code {` and `@functions {` start C# blocks.
+fn is_razor_block(line: &str) -> bool {
+    line.starts_with("@{")
+        || ((line.starts_with("@code") || line.starts_with("@functions")) && line.ends_with('{'))
+}
+
+// TODO(cgag): braces in strings and comments throw this off
+fn brace_depth(line: &str) -> i32 {
+    line.chars().fold(0, |depth, c| match c {
+        '{' => depth + 1,
+        '}' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// The fence character, fence length and language of a Markdown code fence like
+/// ```` ```rust ````, `~~~ python` or ```` ```{.haskell} ````.
+fn open_fence(line: &str) -> Option<(char, usize, &str)> {
+    let fence = line.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = line.len() - line.trim_start_matches(fence).len();
+    let info = line[len..].trim();
+    // ```inline code``` in a paragraph isn't a fence
+    if len < 3 || (fence == '`' && info.contains('`')) {
+        return None;
+    }
+    let lang = info.split(|c: char| c.is_whitespace() || c == ',')
+        .next()
+        .unwrap_or("")
+        .trim_matches(|c| c == '{' || c == '}' || c == '.');
+    Some((fence, len, lang))
+}
+
+/// The language of a reStructuredText `.. code-block:: python`, `.. code:: python` or
+/// `.. sourcecode:: python` directive.
+fn code_directive(line: &str) -> Option<&str> {
+    let directive = line.strip_prefix("..")?.trim_start();
+    ["code-block::", "sourcecode::", "code::"].iter()
+        .find_map(|name| directive.strip_prefix(name))
+        .map(str::trim)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Prose is documentation, so every line of it that isn't blank is a doc comment.
+pub fn count_prose(prose: &str, lengths: &mut LineLengths) -> Count {
+    let mut c = Count::default();
+    for line in text_lines(prose) {
+        c.lines += 1;
+        lengths.add(line);
+        if line.trim().is_empty() {
+            c.blank += 1;
+        } else {
+            c.comment += 1;
+            c.doc += 1;
+        }
+    }
+    c
+}
@@ -0,0 +1,115 @@
+// Turning a file's bytes into text. Most source code is UTF-8, but Windows editors save
+// UTF-16 and UTF-8 with a byte order mark, and old C files are often Latin-1. A file
+// can't say which legacy encoding it's in, so Latin-1 is the guess for anything that
+// isn't valid UTF-8: every byte is a character in it, and the lines come out the same
+// whichever 8-bit encoding it really was.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use encoding_rs::{UTF_16BE, UTF_16LE, WINDOWS_1252};
+use memchr::memchr_iter;
+
+use super::*;
+
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// How the bytes of a file are decoded before counting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Goes by the byte order mark if there is one, reads UTF-16 without one if every
+    /// other byte is NUL, then UTF-8 if the file is valid UTF-8, and Latin-1 otherwise.
+    #[default]
+    Auto,
+    /// Only UTF-8. Anything else is a `CountError::InvalidEncoding`.
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, read as its superset Windows-1252 like browsers do.
+    Latin1,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Encoding, String> {
+        match s.to_lowercase().as_str() {
+            "auto"                                 => Ok(Encoding::Auto),
+            "utf-8" | "utf8"                       => Ok(Encoding::Utf8),
+            "utf-16le" | "utf16le"                 => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be"                 => Ok(Encoding::Utf16Be),
+            "latin-1" | "latin1" | "iso-8859-1"
+                | "windows-1252" | "cp1252"        => Ok(Encoding::Latin1),
+            _ => Err(format!("unknown encoding '{}'", s)),
+        }
+    }
+}
+
+/// Decodes a whole file, without its byte order mark. Fails if the file looks binary,
+/// or isn't UTF-8 when `encoding` is `Encoding::Utf8`. A UTF-16 or Latin-1 file gets
+/// a decoded copy, UTF-8 is borrowed.
+pub fn decode_source(bytes: &[u8], encoding: Encoding) -> Result<Cow<'_, str>, CountError> {
+    let text = match encoding {
+        Encoding::Auto => return decode_auto(bytes),
+        Encoding::Utf8 => Cow::Borrowed(utf8(bytes)?),
+        Encoding::Utf16Le => UTF_16LE.decode_with_bom_removal(bytes).0,
+        Encoding::Utf16Be => UTF_16BE.decode_with_bom_removal(bytes).0,
+        Encoding::Latin1 => WINDOWS_1252.decode_without_bom_handling(bytes).0,
+    };
+    if looks_binary(text.as_bytes()) {
+        return Err(CountError::Binary);
+    }
+    Ok(text)
+}
+
+fn decode_auto(bytes: &[u8]) -> Result<Cow<'_, str>, CountError> {
+    if let Some(utf16) = utf16(bytes) {
+        return decode_source(bytes, utf16);
+    }
+    if looks_binary(bytes) {
+        return Err(CountError::Binary);
+    }
+    match utf8(bytes) {
+        Ok(text) => Ok(Cow::Borrowed(text)),
+        Err(_) if bytes.starts_with(UTF8_BOM) => {
+            // the BOM says UTF-8, so the odd bad byte is a typo, not another encoding
+            Ok(String::from_utf8_lossy(&bytes[UTF8_BOM.len()..]))
+        }
+        Err(_) => Ok(WINDOWS_1252.decode_without_bom_handling(bytes).0),
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str, CountError> {
+    let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    std::str::from_utf8(bytes).map_err(|e| {
+        let newlines = memchr_iter(b'\n', &bytes[..e.valid_up_to()]).count();
+        CountError::InvalidEncoding { line: newlines as u32 + 1 }
+    })
+}
+
+/// Whether `bytes` start like UTF-16 text: a byte order mark, or, since source code is
+/// mostly ASCII, a NUL in most of the high bytes and none of the low bytes of the first
+/// block's characters.
+pub fn utf16(bytes: &[u8]) -> Option<Encoding> {
+    if bytes.starts_with(b"\xff\xfe") {
+        return Some(Encoding::Utf16Le);
+    }
+    if bytes.starts_with(b"\xfe\xff") {
+        return Some(Encoding::Utf16Be);
+    }
+
+    let block = &bytes[..bytes.len().min(SNIFF_LEN) & !1];
+    if block.is_empty() {
+        return None;
+    }
+    let chars = block.len() / 2;
+    let nuls = |offset: usize| block.iter().skip(offset).step_by(2).filter(|&&b| b == 0).count();
+    let (even, odd) = (nuls(0), nuls(1));
+    if even == 0 && odd * 2 > chars {
+        Some(Encoding::Utf16Le)
+    } else if odd == 0 && even * 2 > chars {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,81 @@
+// Files that are generated rather than written, and the directories third party code is
+// vendored into. Neither is code anyone on the project wrote, so the CLI leaves them out of
+// the totals unless asked, like GitHub Linguist's generated.rb and vendor.yml do.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use super::*;
+
+/// How many lines at the start of a file are searched for a generated code marker.
+const MARKER_LINES: usize = 20;
+
+/// Minified JavaScript and CSS averages lines longer than this.
+const MINIFIED_LINE_LEN: f64 = 110.0;
+
+/// Files named like this are written by tools: lockfiles, protobuf and gRPC stubs,
+/// minified bundles, Visual Studio designer files and Dart builders.
+const GENERATED_NAMES: &[&str] = &[
+    "cargo.lock", "composer.lock", "flake.lock", "gemfile.lock", "go.sum", "mix.lock",
+    "npm-shrinkwrap.json", "package-lock.json", "pipfile.lock", "pnpm-lock.yaml",
+    "podfile.lock", "poetry.lock", "yarn.lock",
+];
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".min.js", ".min.mjs", ".min.css", ".js.map", ".css.map",
+    ".pb.go", ".pb.cc", ".pb.h", ".pb.swift", "_pb2.py", "_pb2_grpc.py", "_pb.js", "_pb.d.ts",
+    ".designer.cs", ".designer.vb", ".g.dart", ".freezed.dart",
+];
+
+/// Directories that hold other people's code.
+const VENDORED_DIRS: &[&str] = &[
+    "bower_components", "Carthage", "Godeps", "jspm_packages", "node_modules", "Pods",
+    "third-party", "third_party", "thirdparty", "vendor",
+];
+
+/// Whether the file at `filepath` is generated, going by its name, a marker like Go's
+/// "Code generated ... DO NOT EDIT." near the top of `text`, or JavaScript and CSS lines
+/// too long for anyone to have written them. `lengths` are the file's, from
+/// `try_count_lengths`.
+pub fn is_generated(filepath: &str, lang: Lang, text: &str, lengths: &LineLengths) -> bool {
+    let name = Path::new(filepath)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    if GENERATED_NAMES.contains(&name.as_str()) || GENERATED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+        return true;
+    }
+
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| {
+        // only at the start of a comment, so code that looks for markers isn't generated
+        Regex::new(r"(?i)^\W*(@generated\b|code generated .*do not edit|generated by the protocol buffer compiler|<auto-generated|this (file|code) (is|was) (automatically |auto-?)generated|autogenerated file|generated (code|file)\W+do not (edit|modify))")
+            .expect("generated marker regex")
+    });
+    if text_lines(text).take(MARKER_LINES).any(|line| marker.is_match(line)) {
+        return true;
+    }
+
+    is_minified(lang, lengths)
+}
+
+/// Whether a file with lines as long as `lengths` is minified JavaScript or CSS. Only those,
+/// since Markdown with a paragraph on each line is as long and was still written by hand.
+pub fn is_minified(lang: Lang, lengths: &LineLengths) -> bool {
+    (lang == JavaScript || lang == Css) && lengths.mean() > MINIFIED_LINE_LEN
+}
+
+/// Whether any directory in `path` is one third party code is vendored into, like
+/// `vendor` or `node_modules`.
+pub fn is_vendored<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref().parent().is_some_and(|dir| {
+        dir.components().any(|c| c.as_os_str().to_str().is_some_and(is_vendored_dir))
+    })
+}
+
+/// Whether a directory called `name` holds vendored code.
+pub fn is_vendored_dir(name: &str) -> bool {
+    VENDORED_DIRS.contains(&name)
+}
@@ -0,0 +1,90 @@
+// `linguist-language` attributes from the .gitattributes files in the walked tree, which
+// GitHub Linguist uses to override the language of files it gets wrong:
+//
+//   *.inc   linguist-language=PHP
+//   tpl/**  linguist-language=Smarty
+//
+// Patterns match like they do in .gitignore files. As in git, a .gitattributes file
+// deeper in the tree beats one above it, and later lines beat earlier ones.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use loc::{Lang, LanguageRegistry};
+
+pub struct GitAttributes {
+    root: PathBuf,
+    /// The parsed .gitattributes of every directory we've looked in, empty if it has none.
+    dirs: HashMap<PathBuf, Vec<(Gitignore, Lang)>>,
+}
+
+impl GitAttributes {
+    /// Reads .gitattributes files at or below `root`.
+    pub fn new<P: AsRef<Path>>(root: P) -> GitAttributes {
+        GitAttributes { root: root.as_ref().to_path_buf(), dirs: HashMap::new() }
+    }
+
+    /// The language the .gitattributes files set for the file at `path`, if any.
+    pub fn language(&mut self, languages: &LanguageRegistry, path: &Path) -> Option<Lang> {
+        let dirs: Vec<&Path> = path.ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root))
+            .collect();
+
+        let mut found = None;
+        for dir in dirs.into_iter().rev() {
+            let attributes = self.dirs.entry(dir.to_path_buf())
+                .or_insert_with(|| parse(languages, dir));
+            for (pattern, lang) in attributes.iter() {
+                if pattern.matched(path, false).is_ignore() {
+                    found = Some(*lang);
+                }
+            }
+        }
+        found
+    }
+}
+
+fn parse(languages: &LanguageRegistry, dir: &Path) -> Vec<(Gitignore, Lang)> {
+    let text = match fs::read_to_string(dir.join(".gitattributes")) {
+        Ok(text) => text,
+        Err(_) => return vec![],
+    };
+
+    let mut attributes = vec![];
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let pattern = match fields.next() {
+            Some(pattern) => pattern,
+            None => continue,
+        };
+        // TODO(cgag): warn about languages we don't know?
+        // the last one wins
+        let lang = fields.rev()
+            .filter_map(|attr| attr.strip_prefix("linguist-language="))
+            .filter_map(|name| languages.lookup(name))
+            .map(|language| language.lang)
+            .next();
+        let lang = match lang {
+            Some(lang) => lang,
+            None => continue,
+        };
+
+        // one matcher per line, so a later line can win over an earlier one
+        let mut builder = GitignoreBuilder::new(dir);
+        if builder.add_line(None, pattern).is_err() {
+            continue;
+        }
+        if let Ok(matcher) = builder.build() {
+            attributes.push((matcher, lang));
+        }
+    }
+    attributes
+}
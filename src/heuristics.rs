@@ -0,0 +1,108 @@
+// Content heuristics for extensions that more than one language uses, in the spirit of
+// GitHub Linguist's heuristics.yml. Each ambiguous extension has a list of rules, tried in
+// order: the first whose pattern matches anywhere in the start of the file picks the
+// language. If none match, the file is the language the extension usually means.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use Lang;
+use Lang::*;
+
+/// How much of a file the rules look at.
+pub const HEAD_LEN: usize = 16 * 1024;
+
+/// The language picked for a file with an ambiguous extension, and the rule that decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disambiguation {
+    pub lang: Lang,
+    /// What the rule looks for, like "#import or @interface". "default" if no rule
+    /// matched and the extension's usual language was used.
+    pub rule: &'static str,
+}
+
+struct Heuristic {
+    extensions: &'static [&'static str],
+    /// The language the extension is registered to.
+    default: Lang,
+    rules: &'static [Rule],
+}
+
+struct Rule {
+    lang: Lang,
+    name: &'static str,
+    pattern: &'static str,
+}
+
+const fn rule(lang: Lang, name: &'static str, pattern: &'static str) -> Rule {
+    Rule { lang, name, pattern }
+}
+
+static HEURISTICS: &[Heuristic] = &[
+    Heuristic { extensions: &["d"], default: D, rules: &[
+        rule(Makefile, "make dependency rule", r"(?m)^\S+\.(o|obj|d)\s*:"),
+        rule(D, "module or import", r"(?m)^\s*(module|import)\s+[\w.]+\s*;"),
+    ]},
+    Heuristic { extensions: &["fs"], default: FSharp, rules: &[
+        rule(Glsl, "#version, uniform or void main()",
+             r"(?m)^\s*(#version\s+\d+|(uniform|varying|attribute|precision)\s|layout\s*\(|void\s+main\s*\(\s*(void)?\s*\))"),
+        rule(FSharp, "open, let, module or type", r"(?m)^\s*(open|let|module|namespace|type)\s"),
+    ]},
+    Heuristic { extensions: &["h"], default: CCppHeader, rules: &[
+        rule(ObjectiveC, "#import or @interface",
+             r"(?m)^\s*(#import\b|@(interface|protocol|property|class|end)\b)"),
+        rule(Cpp, "class, namespace, template or a C++ standard header",
+             r"(?m)^\s*(class\s+\w+\s*[:{]|namespace\b|template\s*<|(public|private|protected)\s*:|#include\s*<(iostream|string|vector|map|memory|algorithm|cstdint|cstdio|cstdlib|cstring)>)"),
+    ]},
+    Heuristic { extensions: &["m"], default: ObjectiveC, rules: &[
+        rule(ObjectiveC, "#import or @implementation",
+             r"(?m)^\s*(#(import|include|define)\b|@(interface|implementation|protocol|property|synthesize|end)\b)"),
+        rule(Octave, "endfunction or another Octave end keyword",
+             r"(?m)^\s*(endfunction|endif|endwhile|endfor|end_try_catch|end_unwind_protect)\b"),
+        rule(Matlab, "function, classdef or % comment", r"(?m)^\s*(function\b|classdef\b|%)"),
+    ]},
+    Heuristic { extensions: &["pl"], default: Perl, rules: &[
+        rule(Perl, "use strict, use warnings, my, sub or package",
+             r"(?m)\buse\s+(strict|warnings|v?5)\b|^\s*(my|our|sub|package)\s"),
+        rule(Prolog, ":- directive or rule", r"(?m)^[^#%]*:-"),
+    ]},
+    Heuristic { extensions: &["pro"], default: Prolog, rules: &[
+        rule(QMake, "qmake variable assignment",
+             r"(?m)^\s*(QT|CONFIG|TARGET|TEMPLATE|SOURCES|HEADERS|FORMS|INCLUDEPATH|LIBS|DEFINES)\s*[-+*]?="),
+        rule(Prolog, ":- directive or rule", r"(?m)^[^#%]*:-"),
+    ]},
+    Heuristic { extensions: &["v"], default: Coq, rules: &[
+        rule(Verilog, "endmodule, always @ or assign",
+             r"(?m)\bendmodule\b|^\s*(always\s*@|assign\s|wire\s|reg\s)"),
+        rule(Coq, "Require, Theorem, Definition or Proof",
+             r"(?m)^\s*(Require|Import|Theorem|Lemma|Definition|Inductive|Fixpoint|Proof|Qed)\b"),
+    ]},
+];
+
+fn compiled() -> &'static [Vec<Regex>] {
+    static COMPILED: OnceLock<Vec<Vec<Regex>>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        HEURISTICS.iter()
+            .map(|h| h.rules.iter().map(|r| Regex::new(r.pattern).expect("heuristic pattern")).collect())
+            .collect()
+    })
+}
+
+/// The language the extension usually means, if the extension is ambiguous.
+pub fn default_for(ext: &str) -> Option<Lang> {
+    HEURISTICS.iter().find(|h| h.extensions.contains(&ext)).map(|h| h.default)
+}
+
+/// Picks the language of a file with the lowercase extension `ext` from its contents.
+/// None if the extension isn't ambiguous.
+pub fn disambiguate(ext: &str, contents: &[u8]) -> Option<Disambiguation> {
+    let i = HEURISTICS.iter().position(|h| h.extensions.contains(&ext))?;
+    let heuristic = &HEURISTICS[i];
+    let head = String::from_utf8_lossy(&contents[..contents.len().min(HEAD_LEN)]);
+
+    let matched = heuristic.rules.iter().zip(&compiled()[i])
+        .find(|&(_, regex)| regex.is_match(&head))
+        .map(|(rule, _)| Disambiguation { lang: rule.lang, rule: rule.name });
+    Some(matched.unwrap_or(Disambiguation { lang: heuristic.default, rule: "default" }))
+}
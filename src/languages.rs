@@ -0,0 +1,700 @@
+// The language registry: every language loc knows about, and how to recognize its files.
+// It starts out with the built-in table from builtin.rs, and user defined languages can be
+// added at runtime from TOML or JSON files:
+//
+//   [[language]]
+//   name              = "Frob"
+//   extensions        = ["frob"]
+//   filenames         = ["Frobfile"]
+//   filename_patterns = ["Frobfile.*"]
+//   shebangs          = ["frob"]
+//   line_comments     = ["#"]
+//   block_comments    = [["{#", "#}"]]
+//   nested            = true
+//   spaced_comments   = true              # a comment after code has to follow whitespace
+//   strings           = [{ start = '"', end = '"', escape = '\', multiline = true }]
+//   doc_comments      = ["##", "{#!"]
+//   literate          = "bird"            # or "latex" or "indented", for literate programs
+//
+// A definition whose name matches a built-in language (like "C") overrides it instead:
+// its extensions, filenames and shebangs are mapped to the built-in language, and any
+// comment fields it sets replace the built-in ones.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde_json;
+use toml;
+
+use builtin::BUILTIN;
+use super::*;
+
+/// Everything loc knows about one language.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Language {
+    #[serde(skip)]
+    pub lang: Lang,
+    pub name: String,
+    /// Lowercase, without the dot.
+    pub extensions: Vec<String>,
+    /// File names that identify the language regardless of extension, matched case
+    /// insensitively.
+    pub filenames: Vec<String>,
+    /// Patterns like "*makefile*" for the names of files that don't have one of
+    /// `filenames`, see `LanguageRegistry::by_filename`.
+    pub filename_patterns: Vec<String>,
+    /// Interpreter names, like "python3" for both `#!/usr/bin/python3` and
+    /// `#!/usr/bin/env python3`.
+    pub shebangs: Vec<String>,
+    pub line_comments: Vec<String>,
+    pub block_comments: Vec<(String, String)>,
+    /// Whether block comments nest, see `Syntax`.
+    pub nested: bool,
+    /// Whether a line comment after code has to follow whitespace, see `Syntax`.
+    pub spaced_comments: bool,
+    pub strings: Vec<QuoteDef>,
+    pub doc_comments: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub literate: Option<Literate>,
+}
+
+/// An owned `Quote`, as written in a language file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuoteDef {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub escape: Option<String>,
+    #[serde(default)]
+    pub multiline: bool,
+}
+
+impl QuoteDef {
+    fn quote(&self) -> Quote<'_> {
+        Quote {
+            start: &self.start,
+            end: &self.end,
+            escape: self.escape.as_deref(),
+            multiline: self.multiline,
+        }
+    }
+}
+
+impl Language {
+    pub fn syntax(&self) -> Syntax<'_> {
+        Syntax {
+            line_comments: self.line_comments.iter().map(|s| s.as_str()).collect(),
+            block_comments: self.block_comments.iter()
+                .map(|(start, end)| (start.as_str(), end.as_str()))
+                .collect(),
+            nested: self.nested,
+            spaced_comments: self.spaced_comments,
+            strings: self.strings.iter().map(QuoteDef::quote).collect(),
+            doc_comments: self.doc_comments.iter().map(|s| s.as_str()).collect(),
+            literate: self.literate,
+        }
+    }
+}
+
+/// A language definition as written in a language file. Everything but the name is
+/// optional, and comment fields that are left out keep their built-in values when
+/// overriding a built-in language.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageDef {
+    pub name: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub filenames: Vec<String>,
+    #[serde(default)]
+    pub filename_patterns: Vec<String>,
+    #[serde(default)]
+    pub shebangs: Vec<String>,
+    pub line_comments: Option<Vec<String>>,
+    pub block_comments: Option<Vec<(String, String)>>,
+    pub nested: Option<bool>,
+    pub spaced_comments: Option<bool>,
+    pub strings: Option<Vec<QuoteDef>>,
+    pub doc_comments: Option<Vec<String>>,
+    pub literate: Option<Literate>,
+}
+
+#[derive(Deserialize)]
+struct LanguageFile {
+    #[serde(default)]
+    language: Vec<LanguageDef>,
+}
+
+#[derive(Debug)]
+pub enum LanguageFileError {
+    Io(io::Error),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for LanguageFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LanguageFileError::Io(ref e) => write!(f, "{}", e),
+            LanguageFileError::Parse(ref e) => write!(f, "{}", e),
+            LanguageFileError::Invalid(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for LanguageFileError {}
+
+impl From<io::Error> for LanguageFileError {
+    fn from(e: io::Error) -> LanguageFileError {
+        LanguageFileError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LanguageRegistry {
+    languages: Vec<Language>,
+    by_lang: HashMap<Lang, usize>,
+    by_name: HashMap<String, usize>,
+    by_ext: HashMap<String, usize>,
+    by_filename: HashMap<String, usize>,
+    /// Lowercase, in the order they're tried.
+    by_pattern: Vec<(String, usize)>,
+    by_shebang: HashMap<String, usize>,
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> LanguageRegistry {
+        LanguageRegistry::new()
+    }
+}
+
+impl LanguageRegistry {
+    /// A registry that only knows the built-in languages.
+    pub fn new() -> LanguageRegistry {
+        let mut registry = LanguageRegistry {
+            languages: Vec::with_capacity(BUILTIN.len()),
+            by_lang: HashMap::new(),
+            by_name: HashMap::new(),
+            by_ext: HashMap::new(),
+            by_filename: HashMap::new(),
+            by_pattern: vec![],
+            by_shebang: HashMap::new(),
+        };
+        for b in BUILTIN {
+            let to_strings = |strs: &[&str]| strs.iter().map(|s| String::from(*s)).collect::<Vec<_>>();
+            registry.insert(Language {
+                lang: b.lang,
+                name: String::from(b.name),
+                extensions: to_strings(b.extensions),
+                filenames: to_strings(b.filenames),
+                filename_patterns: to_strings(b.filename_patterns),
+                shebangs: to_strings(b.shebangs),
+                line_comments: to_strings(b.comments.line),
+                block_comments: b.comments.block.iter()
+                    .map(|&(start, end)| (String::from(start), String::from(end)))
+                    .collect(),
+                nested: b.comments.nested,
+                spaced_comments: b.comments.spaced,
+                strings: b.strings.iter()
+                    .map(|q| QuoteDef {
+                        start: String::from(q.start),
+                        end: String::from(q.end),
+                        escape: q.escape.map(String::from),
+                        multiline: q.multiline,
+                    })
+                    .collect(),
+                doc_comments: to_strings(b.doc_comments),
+                literate: b.literate,
+            });
+        }
+        registry
+    }
+
+    /// The built-in registry shared by the free functions like `lang_from_ext`.
+    pub fn builtin() -> &'static LanguageRegistry {
+        static BUILTIN_REGISTRY: OnceLock<LanguageRegistry> = OnceLock::new();
+        BUILTIN_REGISTRY.get_or_init(LanguageRegistry::new)
+    }
+
+    fn insert(&mut self, language: Language) -> usize {
+        let i = self.languages.len();
+        self.by_lang.insert(language.lang, i);
+        self.by_name.insert(language.name.to_lowercase(), i);
+        for ext in &language.extensions {
+            self.by_ext.insert(ext.clone(), i);
+        }
+        for filename in &language.filenames {
+            self.by_filename.insert(filename.to_lowercase(), i);
+        }
+        for pattern in &language.filename_patterns {
+            self.by_pattern.push((pattern.to_lowercase(), i));
+        }
+        for shebang in &language.shebangs {
+            self.by_shebang.insert(shebang.clone(), i);
+        }
+        self.languages.push(language);
+        i
+    }
+
+    /// Adds every definition in a TOML file, or a JSON file if the path ends in `.json`.
+    pub fn load_file(&mut self, path: &Path) -> Result<(), LanguageFileError> {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+
+        let file: LanguageFile = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents).map_err(|e| LanguageFileError::Parse(e.to_string()))?
+        } else {
+            toml::from_str(&contents).map_err(|e| LanguageFileError::Parse(e.to_string()))?
+        };
+
+        for def in file.language {
+            self.add(def)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a definition, or merges it into the language with the same name. Extensions,
+    /// filenames and shebangs it claims are taken away from whichever language had them.
+    pub fn add(&mut self, def: LanguageDef) -> Result<(), LanguageFileError> {
+        validate(&def)?;
+
+        let i = match self.by_name.get(&def.name.to_lowercase()) {
+            Some(&i) => i,
+            None => self.insert(Language {
+                // Leaked so Lang can stay Copy. Definitions are loaded once at startup.
+                lang: Custom(Box::leak(def.name.clone().into_boxed_str())),
+                name: def.name.clone(),
+                extensions: vec![],
+                filenames: vec![],
+                filename_patterns: vec![],
+                shebangs: vec![],
+                line_comments: vec![],
+                block_comments: vec![],
+                nested: false,
+                spaced_comments: false,
+                strings: vec![],
+                doc_comments: vec![],
+                literate: None,
+            }),
+        };
+
+        for ext in def.extensions {
+            let ext = ext.trim_start_matches('.').to_lowercase();
+            claim(&mut self.languages, &mut self.by_ext, i, ext, str::to_owned, |l| &mut l.extensions);
+        }
+        for filename in def.filenames {
+            claim(&mut self.languages, &mut self.by_filename, i, filename, str::to_lowercase, |l| &mut l.filenames);
+        }
+        for pattern in def.filename_patterns {
+            // tried before the patterns that were there already
+            let key = pattern.to_lowercase();
+            if let Some(j) = self.by_pattern.iter().position(|(p, _)| *p == key) {
+                let (_, old) = self.by_pattern.remove(j);
+                self.languages[old].filename_patterns.retain(|p| p.to_lowercase() != key);
+            }
+            self.by_pattern.insert(0, (key, i));
+            self.languages[i].filename_patterns.push(pattern);
+        }
+        for shebang in def.shebangs {
+            claim(&mut self.languages, &mut self.by_shebang, i, shebang, str::to_owned, |l| &mut l.shebangs);
+        }
+
+        let language = &mut self.languages[i];
+        if let Some(line_comments) = def.line_comments {
+            language.line_comments = line_comments;
+        }
+        if let Some(block_comments) = def.block_comments {
+            language.block_comments = block_comments;
+        }
+        if let Some(nested) = def.nested {
+            language.nested = nested;
+        }
+        if let Some(spaced_comments) = def.spaced_comments {
+            language.spaced_comments = spaced_comments;
+        }
+        if let Some(strings) = def.strings {
+            language.strings = strings;
+        }
+        if let Some(doc_comments) = def.doc_comments {
+            language.doc_comments = doc_comments;
+        }
+        if def.literate.is_some() {
+            language.literate = def.literate;
+        }
+        Ok(())
+    }
+
+    /// Every language, built-in ones first.
+    pub fn languages(&self) -> impl Iterator<Item = &Language> {
+        self.languages.iter()
+    }
+
+    pub fn get(&self, lang: Lang) -> Option<&Language> {
+        self.by_lang.get(&lang).map(|&i| &self.languages[i])
+    }
+
+    /// Case insensitive: "c++" finds C++.
+    pub fn by_name(&self, name: &str) -> Option<&Language> {
+        self.by_name.get(&name.to_lowercase()).map(|&i| &self.languages[i])
+    }
+
+    /// With or without the dot: "rs" and ".rs" both find Rust.
+    pub fn by_extension(&self, ext: &str) -> Option<&Language> {
+        self.by_ext.get(&ext.trim_start_matches('.').to_lowercase()).map(|&i| &self.languages[i])
+    }
+
+    /// Case insensitive: "cmakelists.txt" finds CMake. A file name that isn't one of a
+    /// language's `filenames` can match one of its `filename_patterns`, where `*` is any
+    /// number of characters, so "Makefile.am" finds Makefile.
+    pub fn by_filename(&self, filename: &str) -> Option<&Language> {
+        let filename = filename.to_lowercase();
+        self.by_filename.get(&filename)
+            .or_else(|| self.by_pattern.iter().find(|(p, _)| matches_pattern(p, &filename)).map(|(_, i)| i))
+            .map(|&i| &self.languages[i])
+    }
+
+    /// Takes either a whole shebang line ("#!/usr/bin/env python3") or just the
+    /// interpreter ("python3"). A version the interpreter isn't listed with is ignored,
+    /// so "python3.11" finds Python.
+    pub fn by_shebang(&self, shebang: &str) -> Option<&Language> {
+        let interpreter = if shebang.starts_with("#!") {
+            shebang_interpreter(shebang)?
+        } else {
+            shebang
+        };
+        let unversioned = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        self.by_shebang.get(interpreter)
+            .or_else(|| self.by_shebang.get(unversioned))
+            .map(|&i| &self.languages[i])
+    }
+
+    /// Looks a language up by any of the names editors and tools use for it: its name,
+    /// an extension, a file name or an interpreter, so "Python", "py" and "python3" all
+    /// find Python. Vim and Emacs names like "shell-script" work too.
+    pub fn lookup(&self, name: &str) -> Option<&Language> {
+        let name = name.trim_end_matches("-mode");
+        self.by_extension(name)
+            .or_else(|| self.by_name(name))
+            .or_else(|| self.by_name(&name.replace('-', " ")))
+            .or_else(|| self.by_filename(name))
+            .or_else(|| self.by_shebang(name))
+            .or_else(|| {
+                let &(_, lang) = ALIASES.iter().find(|(alias, _)| alias.eq_ignore_ascii_case(name))?;
+                self.get(lang)
+            })
+    }
+
+    /// Works out the language of a file from its name, then its shebang if it doesn't have
+    /// an extension, and its contents if the extension is ambiguous (see `disambiguate`).
+    /// A Vim or Emacs modeline beats a name that doesn't settle the language, see
+    /// `detect_buffer`. Only the start and end of the file are read, and only if its name
+    /// doesn't settle the language.
+    pub fn detect(&self, filepath: &str) -> Lang {
+        match self.detect_by_name(filepath) {
+            Some(lang) => lang,
+            None => self.detect_buffer(filepath, &read_ends(Path::new(filepath))),
+        }
+    }
+
+    /// The language of a file going by its name alone, if that settles it: `Unrecognized`
+    /// for an extension no language claims, other than generic ones like `.inc`. None if the
+    /// file's contents could change it, because it has no extension, an ambiguous or a
+    /// generic one, or a modeline could pick a close relative (see `detect_buffer`).
+    pub fn detect_by_name(&self, filepath: &str) -> Option<Lang> {
+        let (named, ext) = self.by_path(Path::new(filepath));
+        let has_relatives = RELATIVES.iter().any(|family| family.contains(&named));
+        if settles(named, &ext) && !has_relatives { Some(named) } else { None }
+    }
+
+    /// Works out the language of the file at `filepath` like `detect` and reads all of it,
+    /// without reading it twice. A file whose name settles that it isn't code isn't opened,
+    /// and comes back `Unrecognized` and empty.
+    pub fn read_detected(&self, filepath: &str) -> Result<(Lang, Vec<u8>), CountError> {
+        let lang = match self.detect_by_name(filepath) {
+            Some(lang) => lang,
+            None => {
+                return match read_source(File::open(filepath)?) {
+                    Ok(bytes) => Ok((self.detect_buffer(filepath, &bytes), bytes)),
+                    // a binary without an extension isn't source code we couldn't count
+                    Err(CountError::Binary) if self.by_path(Path::new(filepath)).0 == Unrecognized => {
+                        Ok((Unrecognized, vec![]))
+                    }
+                    Err(e) => Err(e),
+                };
+            }
+        };
+        if lang == Unrecognized {
+            return Ok((lang, vec![]));
+        }
+        Ok((lang, read_source(File::open(filepath)?)?))
+    }
+
+    /// Like `detect`, but the modeline, shebang and contents are read from `contents`
+    /// instead of from disk. A modeline is only used when the file's name leaves its
+    /// language open: it has no extension, a generic one no language claims like `.inc`, an
+    /// ambiguous one or `.txt`, or the modeline names a close relative, like C++ for a `.h`
+    /// or `.c` file.
+    pub fn detect_buffer(&self, filepath: &str, contents: &[u8]) -> Lang {
+        // modelines, shebangs and the heuristics are all looking for ASCII
+        if let Some(utf16) = encoding::utf16(contents) {
+            if let Ok(text) = decode_source(contents, utf16) {
+                return self.detect_buffer(filepath, text.as_bytes());
+            }
+        }
+
+        let path = Path::new(filepath);
+        let (named, ext) = self.by_path(path);
+        let modeline = modeline::modeline_language(contents).and_then(|name| self.lookup(name));
+        if let Some(language) = modeline.filter(|language| !settles(named, &ext) || related(named, language.lang)) {
+            return language.lang;
+        }
+        if named != Unrecognized {
+            return match ext {
+                // only second guess the built-in mapping, not an extension a language file claimed
+                Some(ext) if heuristics::default_for(&ext) == Some(named) => {
+                    heuristics::disambiguate(&ext, contents).map_or(named, |found| found.lang)
+                }
+                _ => named,
+            };
+        }
+
+        let file_name_lower = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if path.extension().is_none() => name.to_lowercase(),
+            _ => return Unrecognized,
+        };
+        let first_line = contents.split(|&b| b == b'\n').next().map(String::from_utf8_lossy);
+        first_line.and_then(|line| self.by_shebang(line.trim_end()))
+            .or_else(|| self.by_extension(&file_name_lower))
+            .map_or(Unrecognized, |language| language.lang)
+    }
+
+    /// The language a file's name gives it, and the lowercase extension that did. Files
+    /// named like one of a language's `filenames` don't need an extension.
+    fn by_path(&self, path: &Path) -> (Lang, Option<String>) {
+        let file_name_lower = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_lowercase(),
+            None => return (Unrecognized, None),
+        };
+        if let Some(language) = self.by_filename(&file_name_lower) {
+            return (language.lang, None);
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => {
+                let ext = ext.to_lowercase();
+                (self.by_extension(&ext).map_or(Unrecognized, |language| language.lang), Some(ext))
+            }
+            None => (Unrecognized, None),
+        }
+    }
+
+    /// The syntax used to count `lang`, taking overrides into account.
+    pub fn syntax(&self, lang: Lang) -> Option<Syntax<'_>> {
+        self.get(lang).map(Language::syntax)
+    }
+
+    /// Like the free function `try_count`, but aware of the added definitions.
+    pub fn try_count(&self, filepath: &str) -> Result<Count, CountError> {
+        let (lang, bytes) = self.read_detected(filepath)?;
+        if lang == Unrecognized {
+            return Err(CountError::UnrecognizedLanguage);
+        }
+        self.try_count_bytes(lang, &bytes)
+    }
+
+    pub fn count_reader<R: Read>(&self, lang: Lang, reader: R) -> Result<Count, CountError> {
+        let bytes = read_source(reader)?;
+        self.try_count_bytes(lang, &bytes)
+    }
+
+    pub fn try_count_bytes(&self, lang: Lang, bytes: &[u8]) -> Result<Count, CountError> {
+        self.try_count_lengths(lang, bytes).map(|(count, _)| count)
+    }
+
+    pub fn try_count_lengths(&self, lang: Lang, bytes: &[u8]) -> Result<(Count, LineLengths), CountError> {
+        self.try_count_text_lengths(lang, &decode(bytes)?, LONG_LINE)
+    }
+
+    /// Like `try_count_lengths`, for text that's already decoded, like `decode_source`
+    /// returns. Lines longer than `long_line` characters are long.
+    pub fn try_count_text_lengths(&self, lang: Lang, text: &str, long_line: u32) -> Result<(Count, LineLengths), CountError> {
+        let syntax = self.syntax(lang).ok_or(CountError::UnrecognizedLanguage)?;
+        let mut lengths = LineLengths::new(long_line);
+        let count = count_syntax(&syntax, text, &mut lengths);
+        Ok((count, lengths))
+    }
+
+    /// Like the free function `try_count_embedded`, but aware of the added definitions, so
+    /// `<script lang="frob">` and ```` ```frob ```` are counted as Frob.
+    pub fn try_count_embedded(&self, lang: Lang, bytes: &[u8]) -> Result<Embedded, CountError> {
+        self.try_count_text_embedded(lang, &decode(bytes)?, LONG_LINE)
+    }
+
+    /// Like `try_count_embedded`, for text that's already decoded.
+    pub fn try_count_text_embedded(&self, lang: Lang, text: &str, long_line: u32) -> Result<Embedded, CountError> {
+        let syntax = self.syntax(lang).ok_or(CountError::UnrecognizedLanguage)?;
+        let mut lengths = LineLengths::new(long_line);
+        if !embedded::is_host(lang) {
+            let host = count_syntax(&syntax, text, &mut lengths);
+            return Ok(Embedded { host, embedded: vec![], lengths });
+        }
+
+        let regions = embedded::split(self, lang, text);
+        let mut host = count_syntax(&syntax, &regions.host, &mut lengths);
+        host.merge(&embedded::count_prose(&regions.prose, &mut lengths));
+        // each block is counted on its own, then added to its language's count
+        let mut embedded: Vec<(Lang, Count)> = vec![];
+        for (lang, block) in regions.embedded {
+            let syntax = self.syntax(lang).ok_or(CountError::UnrecognizedLanguage)?;
+            let count = count_syntax(&syntax, &block, &mut lengths);
+            match embedded.iter_mut().find(|&&mut (l, _)| l == lang) {
+                Some((_, total)) => total.merge(&count),
+                None => embedded.push((lang, count)),
+            }
+        }
+        Ok(Embedded { host, embedded, lengths })
+    }
+}
+
+/// Vim and Emacs names for languages that `lookup` can't find any other way.
+const ALIASES: &[(&str, Lang)] = &[
+    ("batchfile",    Batch),
+    ("cperl",        Perl),
+    ("dosbatch",     Batch),
+    ("elisp",        Lisp),
+    ("emacs-lisp",   Lisp),
+    ("fortran",      FortranModern),
+    ("latex",        Tex),
+    ("objc",         ObjectiveC),
+    ("shell",        BourneShell),
+    ("shell-script", BourneShell),
+    ("vim-script",   VimScript),
+];
+
+/// Points `value` at language `i`, removing it from the language that had it before.
+/// `index` is keyed by `key(value)`, so file names can be matched in any case but listed
+/// as they were written.
+fn claim<F>(languages: &mut [Language],
+            index: &mut HashMap<String, usize>,
+            i: usize,
+            value: String,
+            key: fn(&str) -> String,
+            field: F)
+    where F: Fn(&mut Language) -> &mut Vec<String>
+{
+    let k = key(&value);
+    if let Some(old) = index.insert(k.clone(), i) {
+        field(&mut languages[old]).retain(|v| key(v) != k);
+    }
+    field(&mut languages[i]).push(value);
+}
+
+/// Whether `name` matches `pattern`, where `*` is any number of characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(split) => split,
+        // no *, the whole name has to match
+        None => return rest.is_empty(),
+    };
+    let mut rest = rest;
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn validate(def: &LanguageDef) -> Result<(), LanguageFileError> {
+    let invalid = |msg: &str| Err(LanguageFileError::Invalid(format!("language '{}': {}", def.name, msg)));
+
+    if def.name.trim().is_empty() {
+        return invalid("name can't be empty");
+    }
+    if def.line_comments.iter().flatten().any(|s| s.is_empty()) {
+        return invalid("line comment markers can't be empty");
+    }
+    if def.block_comments.iter().flatten().any(|(start, end)| start.is_empty() || end.is_empty()) {
+        return invalid("block comment markers can't be empty");
+    }
+    if def.strings.iter().flatten().any(|q| q.start.is_empty() || q.end.is_empty()
+                                        || q.escape.as_ref().is_some_and(|e| e.is_empty())) {
+        return invalid("string delimiters and escapes can't be empty");
+    }
+    if def.doc_comments.iter().flatten().any(|s| s.is_empty()) {
+        return invalid("doc comment markers can't be empty");
+    }
+    Ok(())
+}
+
+/// Languages close enough that a modeline can pick one over the other whatever the file's
+/// extension says: C and C++ share headers, and a `.sh` script can be written for zsh.
+const RELATIVES: &[&[Lang]] = &[
+    &[C, Cpp, CCppHeader],
+    &[BourneShell, Zsh],
+];
+
+/// Extensions that say nothing about what's in a file, like `.inc` or `Rakefile.local`'s. A
+/// file with one of these no language claims can still have a modeline. Files with other
+/// unclaimed extensions, like `.png` or `.jar`, aren't opened to look.
+const GENERIC_EXTENSIONS: &[&str] = &[
+    "cfg", "conf", "dist", "in", "inc", "local", "template", "tmpl", "tpl",
+];
+
+/// Whether a file's name, which gave it `lang` going by `ext`, settles its language.
+/// Plain text is where a file nobody named for its language ends up.
+fn settles(lang: Lang, ext: &Option<String>) -> bool {
+    match (lang, ext) {
+        (Unrecognized, Some(ext)) => !GENERIC_EXTENSIONS.contains(&ext.as_str()),
+        (Unrecognized, None) | (Text, _) => false,
+        (lang, Some(ext)) => heuristics::default_for(ext) != Some(lang),
+        (_, None) => true,
+    }
+}
+
+fn related(a: Lang, b: Lang) -> bool {
+    RELATIVES.iter().any(|family| family.contains(&a) && family.contains(&b))
+}
+
+/// How much of each end of a file `read_ends` reads, enough for a shebang and modelines.
+const ENDS_LEN: u64 = 1024;
+
+/// The start and end of a file, or more of the start if it has an ambiguous extension,
+/// as much as the heuristics look at. Empty if it can't be read.
+fn read_ends(path: &Path) -> Vec<u8> {
+    let ambiguous = path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| heuristics::default_for(&ext.to_lowercase()).is_some());
+    let head_len = if ambiguous { heuristics::HEAD_LEN as u64 } else { ENDS_LEN };
+
+    let mut contents = vec![];
+    let _ = read_ends_into(path, head_len, &mut contents);
+    contents
+}
+
+fn read_ends_into(path: &Path, head_len: u64, contents: &mut Vec<u8>) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    (&mut file).take(head_len).read_to_end(contents)?;
+    let read = contents.len() as u64;
+    if len > read {
+        contents.push(b'\n');
+        file.seek(io::SeekFrom::Start(read.max(len.saturating_sub(ENDS_LEN))))?;
+        file.read_to_end(contents)?;
+    }
+    Ok(())
+}
@@ -1,20 +1,48 @@
+extern crate encoding_rs;
 extern crate memchr;
+extern crate regex;
 extern crate smallvec;
-
-use std::path::Path;
-use std::fs::File;
-use std::cmp::{max, min};
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+
+mod builtin;
+mod embedded;
+mod encoding;
+mod generated;
+mod heuristics;
+mod languages;
+mod modeline;
+
+pub use encoding::{decode_source, Encoding};
+pub use generated::{is_generated, is_minified, is_vendored, is_vendored_dir};
+pub use heuristics::Disambiguation;
+pub use languages::{Language, LanguageDef, LanguageFileError, LanguageRegistry, QuoteDef};
+
+use builtin::builtin;
+
+use std::borrow::Cow;
+use std::error::Error;
 use std::fmt;
+use std::io;
 use std::io::prelude::*;
+use std::str::FromStr;
 
-use memchr::memchr;
+use memchr::{memchr, memchr2, memchr2_iter, memchr3};
 use smallvec::*;
 
 // Why is it called partialEq?
-#[derive(Debug, PartialEq, Default, Clone)]
+#[derive(Debug, PartialEq, Default, Clone, Serialize)]
 pub struct Count {
     pub code:    u32,
     pub comment: u32,
+    /// Comment lines that are documentation, like Rust's `///` or Javadoc's `/**`.
+    /// These are counted in `comment` too.
+    pub doc:     u32,
+    /// Lines with both code and a comment, like `x = 1; // reset`. Counted in `code` too,
+    /// see `with_mixed_lines` to count them elsewhere.
+    pub mixed:   u32,
     pub blank:   u32,
     pub lines:   u32,
 }
@@ -23,13 +51,123 @@ impl Count {
     pub fn merge(&mut self, o: &Count) {
         self.code    += o.code;
         self.comment += o.comment;
+        self.doc     += o.doc;
+        self.mixed   += o.mixed;
         self.blank   += o.blank;
         self.lines   += o.lines;
     }
+
+    /// The totals with the mixed lines, which counting puts in `code`, counted where
+    /// `mixed_lines` says. `self` is left as it was counted, so this can be called on it
+    /// again with another `MixedLines`.
+    pub fn with_mixed_lines(&self, mixed_lines: MixedLines) -> Count {
+        let mut count = self.clone();
+        match mixed_lines {
+            MixedLines::Code => {}
+            MixedLines::Comment => {
+                count.code    -= count.mixed;
+                count.comment += count.mixed;
+            }
+            MixedLines::Both => count.comment += count.mixed,
+        }
+        count
+    }
 }
 
+/// Lines longer than this many characters are long unless `--long-line` says otherwise,
+/// see `LineLengths`.
+pub const LONG_LINE: u32 = 120;
+
+/// How long the lines of a file are, in characters. Worked out while counting, see
+/// `try_count_lengths`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LineLengths {
+    pub lines:     u32,
+    /// The longest line.
+    pub max:       u32,
+    /// The length of all the lines together.
+    pub total:     u64,
+    /// Lines longer than `long_line`.
+    pub long:      u32,
+    /// How many characters a line can have before it's long, `LONG_LINE` by default.
+    pub long_line: u32,
+}
+
+impl Default for LineLengths {
+    fn default() -> LineLengths {
+        LineLengths::new(LONG_LINE)
+    }
+}
+
+impl LineLengths {
+    /// No lines yet, and lines longer than `long_line` are long.
+    pub fn new(long_line: u32) -> LineLengths {
+        LineLengths { lines: 0, max: 0, total: 0, long: 0, long_line }
+    }
+
+    fn add(&mut self, line: &str) {
+        // most lines are ASCII, whose characters are bytes
+        let len = if line.is_ascii() { line.len() } else { line.chars().count() } as u32;
+        self.lines += 1;
+        self.max = self.max.max(len);
+        self.total += u64::from(len);
+        if len > self.long_line {
+            self.long += 1;
+        }
+    }
+
+    pub fn merge(&mut self, o: &LineLengths) {
+        self.lines += o.lines;
+        self.max = self.max.max(o.max);
+        self.total += o.total;
+        self.long += o.long;
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.lines == 0 { 0.0 } else { self.total as f64 / f64::from(self.lines) }
+    }
+}
+
+/// The count of a file that can contain other languages, like a Vue component's
+/// `<script>` and `<style>` blocks.
+#[derive(Debug, PartialEq, Default, Clone)]
+pub struct Embedded {
+    /// The lines in the file's own language, including the tags around embedded blocks.
+    pub host:     Count,
+    /// One count per embedded language, in the order they first appear.
+    pub embedded: Vec<(Lang, Count)>,
+    /// The lengths of all the file's lines, whatever their language.
+    pub lengths: LineLengths,
+}
+
+/// Where lines with both code and a comment are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MixedLines {
+    #[default]
+    Code,
+    Comment,
+    /// Counted as both code and comment, so code + comment + blank can add up to more
+    /// than the number of lines.
+    Both,
+}
+
+impl FromStr for MixedLines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MixedLines, String> {
+        match s {
+            "code"    => Ok(MixedLines::Code),
+            "comment" => Ok(MixedLines::Comment),
+            "both"    => Ok(MixedLines::Both),
+            _ => Err(format!("unknown mixed line mode '{}'", s)),
+        }
+    }
+}
+
+#[derive(Serialize)]
 pub struct LangTotal {
     pub files: u32,
+    #[serde(flatten)]
     pub count: Count,
 }
 
@@ -75,6 +213,7 @@ pub enum Lang {
     Elixir,
     Elm,
     Erlang,
+    Fish,
     Forth,
     FortranLegacy,
     FortranModern,
@@ -102,16 +241,22 @@ pub enum Lang {
     LinkerScript,
     Lean,
     Lisp,
+    LiterateAgda,
+    LiterateCoffeeScript,
+    LiterateHaskell,
+    LiterateIdris,
     Lua,
     Make,
     Makefile,
     Markdown,
+    Matlab,
     Mustache,
     Nim,
     Nix,
     OCaml,
     ObjectiveC,
     ObjectiveCpp,
+    Octave,
     OpenCl,
     Oz,
     Pascal,
@@ -127,6 +272,7 @@ pub enum Lang {
     Python,
     Qcl,
     Qml,
+    QMake,
     R,
     Razor,
     Reason,
@@ -152,6 +298,7 @@ pub enum Lang {
     TypeScript,
     Tsx,
     UnrealScript,
+    Verilog,
     VimScript,
     Vue,
     Wolfram,
@@ -161,6 +308,8 @@ pub enum Lang {
     Zig,
     Zsh,
     Haxe,
+    /// A language loaded at runtime from a language definition file, see `LanguageRegistry`.
+    Custom(&'static str),
     Unrecognized,
 }
 use self::Lang::*;
@@ -168,129 +317,9 @@ use self::Lang::*;
 impl Lang {
     pub fn to_s(&self) -> &str {
         match *self {
-            ActionScript     => "ActionScript",
-            Ada              => "Ada",
-            Agda             => "Agda",
-            AmbientTalk      => "AmbientTalk",
-            Asp              => "ASP",
-            AspNet           => "ASP.NET",
-            Assembly         => "Assembly",
-            Autoconf         => "Autoconf",
-            Awk              => "Awk",
-            Batch            => "Batch",
-            BourneShell      => "Bourne Shell",
-            C                => "C",
-            CCppHeader       => "C/C++ Header",
-            CMake            => "CMake",
-            CSharp           => "C#",
-            CShell           => "C Shell",
-            Clojure          => "Clojure",
-            ClojureScript    => "ClojureScript",
-            ClojureC         => "ClojureC",
-            CoffeeScript     => "CoffeeScript",
-            ColdFusion       => "ColdFusion",
-            ColdFusionScript => "ColdFusionScript",
-            Coq => "Coq",
-            Cpp => "C++",
-            Crystal => "Crystal",
-            Css => "CSS",
-            CUDA => "CUDA",
-            CUDAHeader => "CUDA Header",
-            D => "D",
-            Dart => "Dart",
-            Dhall => "Dhall",
-            DeviceTree => "DeviceTree",
-            Docker => "Docker",
-            Elixir => "Elixir",
-            Elm => "Elm",
-            Erlang => "Erlang",
-            Forth => "Forth",
-            FortranLegacy => "FORTRAN Legacy",
-            FortranModern => "FORTRAN Modern",
-            FSharp => "F#",
-            Gherkin => "Gherkin",
-            Glsl => "GLSL",
-            Go => "Go",
-            Groovy => "Groovy",
-            Handlebars => "Handlebars",
-            Haskell => "Haskell",
-            Hex => "Hex",
-            Html => "HTML",
-            INI => "INI",
-            Idris => "Idris",
-            IntelHex => "Intel Hex",
-            Isabelle => "Isabelle",
-            Jai => "Jai",
-            Java => "Java",
-            JavaScript => "JavaScript",
-            Json => "JSON",
-            Jsx => "Jsx",
-            Julia => "Julia",
-            Kotlin => "Kotlin",
-            Less => "Less",
-            LinkerScript => "LinkerScript",
-            Lean => "Lean",
-            Lisp => "Lisp",
-            Lua => "Lua",
-            Make => "Make",
-            Makefile => "Makefile",
-            Markdown => "Markdown",
-            Mustache => "Mustache",
-            Nim => "Nim",
-            Nix => "Nix",
-            OCaml => "OCaml",
-            ObjectiveC => "Objective-C",
-            ObjectiveCpp => "Objective-C++",
-            OpenCl => "OpenCL",
-            Oz => "Oz",
-            Pascal => "Pascal",
-            Perl => "Perl",
-            Php => "PHP",
-            Polly => "Polly",
-            PowerShell => "PowerShell",
-            Prolog => "Prolog",
-            Protobuf => "Protobuf",
-            Puppet => "Puppet",
-            PureScript => "PureScript",
-            Pyret => "Pyret",
-            Python => "Python",
-            Qcl => "Qcl",
-            Qml => "Qml",
-            R => "R",
-            Razor => "Razor",
-            Reason           => "Reason",
-            Ron              => "RON",
-            ReStructuredText => "reStructuredText",
-            Ruby             => "Ruby",
-            RubyHtml         => "RubyHtml",
-            Rust             => "Rust",
-            SaltStack        => "SaltStack",
-            Sass             => "Sass",
-            Scala            => "Scala",
-            Sml              => "SML",
-            Solidity         => "Solidity",
-            Sql              => "SQL",
-            Stylus           => "Stylus",
-            Svelte           => "Svelte",
-            Swift            => "Swift",
-            Tcl              => "Tcl",
-            Terraform        => "Terraform",
-            Tex              => "TeX",
-            Text             => "Plain Text",
-            Toml             => "Toml",
-            TypeScript       => "TypeScript",
-            Tsx              => "Typescript JSX",
-            UnrealScript     => "UnrealScript",
-            VimScript        => "VimL",
-            Vue              => "Vue",
-            Wolfram          => "Wolfram",
-            XML              => "XML",
-            Yacc             => "Yacc",
-            Yaml             => "YAML",
-            Zig              => "Zig",
-            Zsh              => "Z Shell",
-            Haxe             => "Haxe",
-            Unrecognized     => "Unrecognized",
+            Custom(name) => name,
+            Unrecognized => "Unrecognized",
+            lang => builtin(lang).expect("every built-in language is in the table").name,
         }
     }
 }
@@ -301,325 +330,349 @@ impl fmt::Display for Lang {
     }
 }
 
+/// The language of a file, going by a Vim or Emacs modeline, its name or extension, or
+/// its shebang if it doesn't have an extension. See `LanguageRegistry::detect`.
 pub fn lang_from_ext(filepath: &str) -> Lang {
-    let path = Path::new(filepath);
-    let file_name_lower = path.file_name()
-        .expect("no filename?")
-        .to_str()
-        .expect("to_str")
-        .to_lowercase();
-
-    let ext = if file_name_lower.contains("makefile") {
-        String::from("makefile")
-    } else if file_name_lower == "dockerfile" {
-        String::from("docker")
-    } else if file_name_lower == "cmakelists.txt" {
-        String::from("cmake")
-    } else {
-        match path.extension() {
-            Some(os_str) => os_str.to_str().expect("path to_str").to_lowercase(),
-            None => {
-                if let Some(ext) = check_shebang(path) {
-                    ext
-                } else {
-                    file_name_lower
-                }
-            }
+    LanguageRegistry::builtin().detect(filepath)
+}
+
+/// Like `lang_from_ext`, but for files that only exist in memory (editor buffers, staged
+/// blobs). Modelines and shebangs are read from `contents` rather than from disk.
+pub fn lang_from_buffer(filepath: &str, contents: &[u8]) -> Lang {
+    LanguageRegistry::builtin().detect_buffer(filepath, contents)
+}
+
+/// Picks the language of a file whose extension several languages use, like `.m` for
+/// Objective-C and MATLAB or `.pl` for Perl and Prolog, from its contents. Returns the
+/// language and the rule that decided it, or None if the extension isn't ambiguous.
+/// `lang_from_ext` and `lang_from_buffer` already do this.
+pub fn disambiguate(filepath: &str, contents: &[u8]) -> Option<Disambiguation> {
+    let ext = std::path::Path::new(filepath).extension()?.to_str()?.to_lowercase();
+    heuristics::disambiguate(&ext, contents)
+}
+
+pub type CounterConfig<'a> = (SmallVec<[&'a str; 3]>, SmallVec<[(&'a str, &'a str); 3]>);
+
+/// The line and block comment markers of a built-in language. Kept for compatibility,
+/// `LanguageRegistry` knows about custom languages and overrides too.
+pub fn counter_config_for_lang<'a>(lang: Lang) -> CounterConfig<'a> {
+    let syntax = Syntax::for_lang(lang).expect("counter_config_for_lang: not a built-in language");
+    (syntax.line_comments, syntax.block_comments)
+}
+
+/// The comment and string syntax of a language: everything the line counter needs to
+/// know about it.
+#[derive(Debug, Clone)]
+pub struct Syntax<'a> {
+    pub line_comments: SmallVec<[&'a str; 3]>,
+    pub block_comments: SmallVec<[(&'a str, &'a str); 3]>,
+    /// Whether a block comment start inside a block comment opens a nested comment that
+    /// needs its own end marker.
+    pub nested: bool,
+    /// Whether a line comment after code has to follow whitespace, like a shell script's
+    /// `#`, so `$#` and `${#list}` are code.
+    pub spaced_comments: bool,
+    /// String literals. Comment markers inside them are ignored. A line comment after
+    /// code is only recognized in languages that have some.
+    pub strings: SmallVec<[Quote<'a>; 4]>,
+    /// Starts of line or block comments that are documentation, like `///` or `/**`.
+    /// A comment that repeats a character of the marker right after it, like `////`,
+    /// `/****` or `/**/`, isn't documentation.
+    pub doc_comments: SmallVec<[&'a str; 4]>,
+    /// Set for literate programs, where prose is the default and code is marked. The
+    /// comment and string syntax isn't used for them.
+    pub literate: Option<Literate>,
+}
+
+/// How a literate program marks its code. Everything else in the file is prose, and
+/// counted as documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Literate {
+    /// Bird tracks, `> main = pure ()`, or `\begin{code}` ... `\end{code}` blocks.
+    Bird,
+    /// Only `\begin{code}` ... `\end{code}` blocks.
+    Latex,
+    /// Lines indented by four spaces or a tab, the code blocks of Markdown.
+    Indented,
+}
+
+/// One kind of string literal, like `"..."`, Python's `"""..."""` or Rust's `r#"..."#`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote<'a> {
+    pub start: &'a str,
+    pub end: &'a str,
+    /// Skips whatever follows it, so `"\""` doesn't end at the second quote.
+    /// None for raw strings.
+    pub escape: Option<&'a str>,
+    /// Whether the string can continue onto the next line. A start marker without an
+    /// end on the same line is taken as code otherwise, so lifetimes like `'a` and
+    /// apostrophes in text don't swallow the rest of the line.
+    pub multiline: bool,
+}
+
+impl<'a> Syntax<'a> {
+    /// The syntax of a built-in language. None for `Unrecognized` and custom languages,
+    /// which only a `LanguageRegistry` knows about.
+    pub fn for_lang(lang: Lang) -> Option<Syntax<'a>> {
+        builtin(lang).map(|b| Syntax {
+            line_comments: b.comments.line.iter().cloned().collect(),
+            block_comments: b.comments.block.iter().cloned().collect(),
+            nested: b.comments.nested,
+            spaced_comments: b.comments.spaced,
+            strings: b.strings.iter().cloned().collect(),
+            doc_comments: b.doc_comments.iter().cloned().collect(),
+            literate: b.literate,
+        })
+    }
+}
+
+/// Why a file couldn't be counted.
+#[derive(Debug)]
+pub enum CountError {
+    /// The file couldn't be opened or read.
+    Io(io::Error),
+    /// A line wasn't valid UTF-8, and the file had to be (see `Encoding::Utf8`). `line`
+    /// is 1-based.
+    InvalidEncoding { line: u32 },
+    /// The file contains NUL bytes or starts with too many control characters, so it's
+    /// almost certainly not source code. See `is_binary`.
+    Binary,
+    /// We don't know what language the file is in, so we don't know how to count it.
+    UnrecognizedLanguage,
+}
+
+impl fmt::Display for CountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CountError::Io(ref e) => write!(f, "i/o error: {}", e),
+            CountError::InvalidEncoding { line } => write!(f, "invalid UTF-8 on line {}", line),
+            CountError::Binary => write!(f, "binary file"),
+            CountError::UnrecognizedLanguage => write!(f, "unrecognized language"),
         }
-    };
-
-    // NOTE(cgag): while we lifted most of this from tokei, we support a few
-    // more extensions in some places, can't just assume it's the same.
-    match &*ext {
-        "4th" | "forth" | "fr" | "frt" | "fth" | "f83" | "fb" | "fpm" | "e4" | "rx" | "ft" => Forth,
-        "ada" | "adb" | "ads" | "pad" => Ada,
-        "agda" => Agda,
-        "as" => ActionScript,
-        "at" => AmbientTalk,
-        "awk" => Awk,
-        "bat" | "btm" | "cmd" => Batch,
-        "c" | "ec" | "pgc" => C,
-        "cc" | "cpp" | "cxx" | "c++" | "pcc" => Cpp,
-        "cfc" => ColdFusionScript,
-        "cmake" => CMake,
-        "cl" => OpenCl,
-        "coffee" => CoffeeScript,
-        "cr" => Crystal,
-        "cs" => CSharp,
-        "csh" => CShell,
-        "css" | "pcss" | "sss" | "postcss" => Css,
-        "cu" => CUDA,
-        "cuh" => CUDAHeader,
-        "d" => D,
-        "dart" => Dart,
-        "dhall" => Dhall,
-        "dts" | "dtsi" => DeviceTree,
-        "docker" => Docker,
-        "el" | "lisp" | "lsp" | "scm" | "ss" | "rkt" => Lisp,
-        "ex" | "exs" => Elixir,
-        "elm" => Elm,
-        "erl" | "hrl" => Erlang,
-        "feature" => Gherkin,
-        "fs" | "fsx" => FSharp,
-        "vert" | "tesc" | "tese" | "geom" | "frag" | "comp" => Glsl,
-        "go" => Go,
-        "groovy" => Groovy,
-        "h" | "hh" | "hpp" | "hxx" => CCppHeader,
-        "hbs" | "handlebars" => Handlebars,
-        "hs" => Haskell,
-        "html" => Html,
-        "idr" | "lidr" => Idris,
-        "ini" => INI,
-        "jai" => Jai,
-        "java" => Java,
-        "jl" => Julia,
-        "js" | "mjs" => JavaScript,
-        "jsx" => Jsx,
-        "kt" | "kts" => Kotlin,
-        "lds" => LinkerScript,
-        "lean" | "hlean" => Lean,
-        "less" => Less,
-        "lua" => Lua,
-        "m" => ObjectiveC,
-        "ml" | "mli" => OCaml,
-        "nb" | "wl" => Wolfram,
-        "sh" => BourneShell,
-        "asa" | "asp" => Asp,
-        "asax" | "ascx" | "asmx" | "aspx" | "master" | "sitemap" | "webinfo" => AspNet,
-        "in" => Autoconf,
-        "clj" => Clojure,
-        "cljs" => ClojureScript,
-        "cljc" => ClojureC,
-        "f" | "for" | "ftn" | "f77" | "pfo" => FortranLegacy,
-        "f03" | "f08" | "f90" | "f95" => FortranModern,
-        "makefile" | "mk" => Makefile,
-        "mm" => ObjectiveCpp,
-        "nim" => Nim,
-        "nix" => Nix,
-        "php" => Php,
-        "pl" | "pm" => Perl,
-        "pp" => Puppet,
-        "qcl" => Qcl,
-        "qml" => Qml,
-        "cshtml" => Razor,
-        "mustache" => Mustache,
-        "oz" => Oz,
-        "p" | "pro" => Prolog,
-        "pas" => Pascal,
-        "hex" => Hex,
-        "ihex" => IntelHex,
-        "json" => Json,
-        "markdown" | "md" => Markdown,
-        "rst" => ReStructuredText,
-        "text" | "txt" => Text,
-
-        "polly" => Polly,
-        "ps1" | "psd1" | "psm1" => PowerShell,
-        "proto" => Protobuf,
-        "purs" => PureScript,
-        "arr" => Pyret,
-        "py" => Python,
-        "r" => R,
-        "rake" | "rb" => Ruby,
-        "re" | "rei" => Reason,
-        "rhtml" | "erb" => RubyHtml,
-        "ron" => Ron,
-        "rs" => Rust,
-        "s" | "asm" => Assembly,
-        "sass" | "scss" => Sass,
-        "sc" | "scala" => Scala,
-        "sls" => SaltStack,
-        "sml" => Sml,
-        "sol" => Solidity,
-        "sql" => Sql,
-        "styl" => Stylus,
-        "svelte" => Svelte,
-        "swift" => Swift,
-        "tcl" => Tcl,
-        "tf" => Terraform,
-        "tex" | "sty" => Tex,
-        "toml" => Toml,
-        "ts" => TypeScript,
-        "tsx" => Tsx,
-        "thy" => Isabelle,
-        "uc" | "uci" | "upkg" => UnrealScript,
-        "v" => Coq,
-        "vim" => VimScript,
-        "vue" => Vue,
-        "xml" => XML,
-        "yaml" | "yml" => Yaml,
-        "y" => Yacc,
-        "zig" => Zig,
-        "zsh" => Zsh,
-        "hx" => Haxe,
-        // Probably dumb to just default to C.
-        _ => Unrecognized,
     }
 }
 
-pub fn counter_config_for_lang<'a>(lang: Lang) -> (SmallVec<[&'a str; 3]>, SmallVec<[(&'a str, &'a str); 3]>) {
-    let c_style      = (smallvec!["//"], smallvec![("/*", "*/")]);
-    let html_style   = (smallvec![],     smallvec![("<!--", "-->")]);
-    let ml_style     = (smallvec![],     smallvec![("(*", "*)")]);
-    let no_comments  = (smallvec![],     smallvec![]);
-    let prolog_style = (smallvec!["%"],  smallvec![("/*", "*/")]);
-    let sh_style     = (smallvec!["#"],  smallvec![]);
-
-    match lang {
-        Ada            => (smallvec!["--"], smallvec![]),
-        Batch          => (smallvec!["REM"], smallvec![]),
-        Erlang | Tex   => (smallvec!["%"], smallvec![]),
-        FortranModern  => (smallvec!["!"], smallvec![]),
-        INI            => (smallvec![";"], smallvec![]),
-        Protobuf | Zig => (smallvec!["//"], smallvec![]),
-        VimScript      => (smallvec!["\""], smallvec![]),
-        Terraform      => (smallvec!["#"], smallvec![("/*", "*/")]),
-        Nix            => (smallvec!["#"], smallvec![("/*", "*/")]),
-
-        // TODO(cgag): Well, some architectures use ;, @, |, etc.  Figure out something
-        // better?
-        Assembly     => (smallvec!["#"], smallvec![("/*", "*/")]),
-        CMake        =>  (smallvec!["#"], smallvec![("#[[", "]]")]),
-        CoffeeScript => (smallvec!["#"], smallvec![("###", "###")]),
-        Crystal      => (smallvec!["#"], smallvec![]),
-        D            => (smallvec!["//"], smallvec![("/*", "*/")]),
-        Docker       => (smallvec!["#"], smallvec![]),
-        Forth        => (smallvec!["\\"], smallvec![("(", ")")]),
-        FSharp       => (smallvec!["//"], smallvec![("(*", "*)")]),
-        Julia        => (smallvec!["#"], smallvec![("#=", "=#")]),
-        Lisp         => (smallvec![";"], smallvec![("#|", "|#")]),
-        Lean         => (smallvec!["--"], smallvec![("/-", "-/")]),
-        Lua          => (smallvec!["--"], smallvec![("--[[", "]]")]),
-        // which one is right? = or =pod?
-        // Perl => SM("#""=", "=cut"),
-        Perl   => (smallvec!["#"], smallvec![("=pod", "=cut")]),
-        Puppet => (smallvec!["#"], smallvec![]),
-        Pyret  => (smallvec!["#"], smallvec![("#|", "|#")]),
-        Python => (smallvec!["#"], smallvec![("'''", "'''")]),
-        Ruby   => (smallvec!["#"], smallvec![("=begin", "=end")]),
-        Sql    => (smallvec!["--"], smallvec![("/*", "*/")]),
-
-        Dhall | Haskell | Idris | Agda | PureScript | Elm => (smallvec!["--"], smallvec![("{-", "-}")]),
-
-        ColdFusion    => (smallvec![],   smallvec![("<!---", "--->")]),
-        Mustache      => (smallvec![],   smallvec![("{{!", "}}")]),
-        Asp           => (smallvec!["'", "REM"], smallvec![]),
-        AspNet        => (smallvec![],   smallvec![("<!--", "-->"), ("<%--", "-->")]),
-        Autoconf      => (smallvec!["#", "dnl"], smallvec![]),
-        FortranLegacy => (smallvec!["c", "C", "!", "*"], smallvec![]),
-        Handlebars    => (smallvec![],   smallvec![("<!--", "-->"), ("{{!", "}}")]),
-        Php           => (smallvec!["#", "//"], smallvec![("/*", "*/")]),
-        PowerShell    => (smallvec!["#"], smallvec![("<#", "#>")]),
-
-        Clojure | ClojureScript | ClojureC => (smallvec![";"], smallvec![]),
-
-        Isabelle => {
-            (
-                smallvec!["--"],
-                // Is that angle bracket utf8?  What's going to happen with that?
-                smallvec![
-                    ("{*", "*}"),
-                    ("(*", "*)"),
-                    ("‹", "›"),
-                    ("\\<open>", "\\<close>"),
-                ],
-            )
+impl Error for CountError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            CountError::Io(ref e) => Some(e),
+            _ => None,
         }
+    }
+}
 
-        Razor  => (smallvec![], smallvec![("<!--", "-->"), ("@*", "*@")]),
-        Pascal => (smallvec!["//", "(*"], smallvec![("{", "}")]),
-        Vue    => (smallvec!["//"], smallvec![("/*", "*/"), ("<!--", "-->")]),
-        Svelte    => (smallvec!["//"], smallvec![("/*", "*/"), ("<!--", "-->")]),
-        Text | Markdown | Json | IntelHex | Hex | ReStructuredText => no_comments,
+impl From<io::Error> for CountError {
+    fn from(e: io::Error) -> CountError {
+        CountError::Io(e)
+    }
+}
 
-        Oz | Prolog => prolog_style,
+/// Like `try_count`, but any file that can't be counted gets an all-zero `Count`.
+pub fn count(filepath: &str) -> Count {
+    try_count(filepath).unwrap_or_default()
+}
 
-        Coq | Sml | Wolfram | OCaml => ml_style,
+pub fn try_count(filepath: &str) -> Result<Count, CountError> {
+    LanguageRegistry::builtin().try_count(filepath)
+}
 
-        Html | Polly | RubyHtml | XML => html_style,
+/// Counts everything `reader` produces as source code in `lang`.
+pub fn count_reader<R: Read>(lang: Lang, reader: R) -> Result<Count, CountError> {
+    let bytes = read_source(reader)?;
+    try_count_bytes(lang, &bytes)
+}
 
-        BourneShell | Make | Awk | CShell | Gherkin | Makefile | Nim | R | SaltStack | Tcl
-        | Toml | Yaml | Zsh | Elixir => sh_style,
+/// How much of a file `is_binary` looks at.
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// Whether `bytes` look like a binary file rather than text, going by their first
+/// `SNIFF_LEN` bytes: any NUL byte, or more than one byte in ten being a control
+/// character that text doesn't use. Bytes above 0x7f are text, they're UTF-8 or Latin-1,
+/// and so is UTF-16, NULs and all.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    let block = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if encoding::utf16(block).is_some() {
+        return false;
+    }
+    if memchr(0, block).is_some() {
+        return true;
+    }
+    let control = block.iter().filter(|&&b| is_control(b)).count();
+    control * 10 > block.len()
+}
 
+/// Control characters other than tab, newlines, form feed, backspace and escape (for
+/// terminal colors).
+fn is_control(b: u8) -> bool {
+    (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x08 | 0x1b)) || b == 0x7f
+}
 
-        // TODO(cgag): not 100% sure that yacc belongs here.
-        AmbientTalk | C | CCppHeader | Rust | Yacc | ActionScript | ColdFusionScript | Css | Cpp | CUDA
-        | CUDAHeader | CSharp | Dart | DeviceTree | Glsl | Go | Jai | Java | JavaScript | Jsx
-        | Kotlin | Less | LinkerScript | ObjectiveC | ObjectiveCpp | OpenCl | Qcl | Sass | Scala | Swift
-        | TypeScript | Tsx | UnrealScript | Stylus | Qml | Haxe | Groovy | Reason | Solidity | Ron => c_style,
+/// Reads everything `reader` produces, but gives up with `CountError::Binary` after the
+/// first block if it looks binary, so a big binary file isn't read in full.
+pub fn read_source<R: Read>(mut reader: R) -> Result<Vec<u8>, CountError> {
+    // TODO(cgag): set the size of this vec to size of the file + a byte? a reddit comment
+    // somewhere says fs::read will do this ofr you.
+    let mut bytes = Vec::with_capacity(SNIFF_LEN);
+    (&mut reader).take(SNIFF_LEN as u64).read_to_end(&mut bytes)?;
+    if is_binary(&bytes) {
+        return Err(CountError::Binary);
+    }
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
 
+/// `is_binary`, or a NUL byte past the first block.
+fn looks_binary(bytes: &[u8]) -> bool {
+    is_binary(bytes) || memchr(0, bytes).is_some()
+}
 
-        Unrecognized => unreachable!(),
-    }
+/// Like `try_count_bytes`, but a buffer that can't be counted gets an all-zero `Count`.
+pub fn count_bytes(lang: Lang, bytes: &[u8]) -> Count {
+    try_count_bytes(lang, bytes).unwrap_or_default()
+}
+
+/// Counts an in-memory buffer of source code in `lang`, without touching the filesystem.
+/// It's decoded with `Encoding::Auto`, to use another encoding `decode_source` it first.
+// TODO(cgag): do we have to worry about the case of single line comments being nested in multis?
+// I dn't think so but i should think about it.
+pub fn try_count_bytes(lang: Lang, bytes: &[u8]) -> Result<Count, CountError> {
+    try_count_lengths(lang, bytes).map(|(count, _)| count)
 }
 
-struct ByteLinesState<'a> {
-    buf: &'a [u8],
-    pos: usize,
+/// Like `try_count_bytes`, and how long the lines are.
+pub fn try_count_lengths(lang: Lang, bytes: &[u8]) -> Result<(Count, LineLengths), CountError> {
+    LanguageRegistry::builtin().try_count_lengths(lang, bytes)
 }
 
-struct ByteLines<'a>(&'a [u8]);
+/// Like `try_count_bytes`, but HTML, Vue, Svelte, Razor and ERB files are split into the
+/// languages embedded in them, and so are the code blocks of Markdown and reStructuredText
+/// files, whose prose is counted as doc comments. Other languages have nothing embedded.
+pub fn try_count_embedded(lang: Lang, bytes: &[u8]) -> Result<Embedded, CountError> {
+    LanguageRegistry::builtin().try_count_embedded(lang, bytes)
+}
+
+fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, CountError> {
+    decode_source(bytes, Encoding::Auto)
+}
+
+/// The lines of `text` without their line endings, which can be `\n`, `\r\n` or the
+/// `\r` of old Mac files. A line ending at the very end doesn't start another line.
+fn text_lines(text: &str) -> TextLines<'_> {
+    TextLines(text)
+}
+
+struct TextLines<'a>(&'a str);
 
-impl<'a> ByteLines<'a> {
-    fn lines(&self) -> ByteLinesState {
-        ByteLinesState {
-            buf: self.0,
-            pos: 0,
+impl<'a> Iterator for TextLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.0.is_empty() {
+            return None;
         }
+        let bytes = self.0.as_bytes();
+        let (line, rest) = match memchr2(b'\n', b'\r', bytes) {
+            Some(i) if bytes[i] == b'\r' && bytes.get(i + 1) == Some(&b'\n') => (&self.0[..i], &self.0[i + 2..]),
+            Some(i) => (&self.0[..i], &self.0[i + 1..]),
+            None => (self.0, ""),
+        };
+        self.0 = rest;
+        Some(line)
     }
 }
 
-impl<'a> Iterator for ByteLinesState<'a> {
-    type Item = &'a [u8];
+/// How the lines of a file end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    /// The file is one line, without an ending.
+    None,
+    Lf,
+    CrLf,
+    /// Old Mac files.
+    Cr,
+    /// More than one kind.
+    Mixed,
+}
 
-    fn next(&mut self) -> Option<&'a [u8]> {
-        match memchr(b'\n', &self.buf[self.pos..self.buf.len()]) {
-            Some(n) => {
-                let start = self.pos;
-                self.pos = self.pos + n + 1;
-                // - 1 to drop \n char
-                Some(&self.buf[start..(self.pos - 1)])
-            }
-            None => {
-                if self.pos == self.buf.len() {
-                    return None;
-                }
-                let start = self.pos;
-                self.pos = self.buf.len();
-                Some(&self.buf[start..self.pos])
-            }
+impl LineEndings {
+    pub fn to_s(self) -> &'static str {
+        match self {
+            LineEndings::None  => "none",
+            LineEndings::Lf    => "lf",
+            LineEndings::CrLf  => "crlf",
+            LineEndings::Cr    => "cr",
+            LineEndings::Mixed => "mixed",
         }
     }
 }
 
-// TODO(cgag): do we have to worry about the case of single line comments being nested in multis?
-// I dn't think so but i should think about it.
-pub fn count(filepath: &str) -> Count {
-    let lang = lang_from_ext(filepath);
-    let (singles, multis) = counter_config_for_lang(lang);
-
-    let mfile = File::open(filepath);
-    let mut file = match mfile {
-        Ok(file) => file,
-        Err(_) => {
-            return Count::default();
+impl fmt::Display for LineEndings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.to_s())
+    }
+}
+
+/// The line endings `text` uses. Works on UTF-8 and Latin-1, decode UTF-16 first (see
+/// `decode_source`).
+pub fn line_endings(text: &[u8]) -> LineEndings {
+    let (mut lf, mut crlf, mut cr) = (false, false, false);
+    let mut after = 0;
+    for i in memchr2_iter(b'\n', b'\r', text) {
+        // the \n of a \r\n
+        if i < after {
+            continue;
         }
-    };
-    // TODO(cgag): set the size of this vec to size of the file + a byte? a reddit comment
-    // somewhere says fs::read will do this ofr you.
-    let mut bytes = vec![];
-    file.read_to_end(&mut bytes).expect("nani?!");
+        if text[i] == b'\n' {
+            lf = true;
+        } else if text.get(i + 1) == Some(&b'\n') {
+            crlf = true;
+            after = i + 2;
+        } else {
+            cr = true;
+        }
+    }
+    match (lf, crlf, cr) {
+        (false, false, false) => LineEndings::None,
+        (true, false, false)  => LineEndings::Lf,
+        (false, true, false)  => LineEndings::CrLf,
+        (false, false, true)  => LineEndings::Cr,
+        _                     => LineEndings::Mixed,
+    }
+}
+
+/// Counts the lines of `text`, adding their lengths to `lengths`.
+fn count_syntax(syntax: &Syntax, text: &str, lengths: &mut LineLengths) -> Count {
+    if let Some(literate) = syntax.literate {
+        return count_literate(literate, text, lengths);
+    }
+
+    let singles = &syntax.line_comments;
+    let multis = &syntax.block_comments;
+    let quotes = &syntax.strings;
+    let docs = &syntax.doc_comments;
+    // A line comment after code is only believable if we can tell it isn't inside a
+    // string, so without any string syntax only comments that start a line count.
+    let trailing_comments = !quotes.is_empty();
+
+    // what can start a marker outside of a block comment, and inside of one
+    let outside = MarkerBytes::new(singles.iter().cloned()
+        .chain(multis.iter().map(|&(start, _)| start))
+        .chain(quotes.iter().map(|q| q.start)));
+    let inside = MarkerBytes::new(multis.iter().map(|&(_, end)| end)
+        .chain(multis.iter().filter(|_| syntax.nested).map(|&(start, _)| start)));
 
     let mut c = Count::default();
     let mut multi_stack: Vec<(&str, &str)> = vec![];
+    // the multiline string the previous line ended inside of, if any
+    let mut open_string: Option<&Quote> = None;
+    // whether the outermost open block comment is documentation
+    let mut doc_block = false;
 
-    'line: for byte_line in ByteLines(&bytes).lines() {
-        let line = match std::str::from_utf8(byte_line) {
-            Ok(s) => s,
-            // TODO(cgag): should we report when this happens?
-            Err(_) => return Count::default(),
-        };
+    'line: for line in text_lines(text) {
         c.lines += 1;
+        lengths.add(line);
 
         let line = line.trim_start();
         // should blanks within a comment count as blank or comment? This counts them as blank.
@@ -628,148 +681,285 @@ pub fn count(filepath: &str) -> Count {
             continue;
         };
 
-        // if we match a single line comment, count it and go onto next line
-        // TODO(cgag): is the multiline comment start symbol ever the shorter one?
-        // if multi_stack.is_empty, then we're not currently in a multiline comment
-        if multi_stack.is_empty() {
-            for single_start in singles.iter() {
-                if line.starts_with(single_start) {
-                    // if this single_start is a prefix of a multi_start,
-                    // make sure that the line doesn't actually start with the multi_start
-                    // TODO(cgag): donm't do this check here
-                    // TODO(cgag): this assumption that the multi-line comment is always the longer one
-                    //             may well be a terrible one
-                    if multis.iter().any(|(m_start, _)| line.starts_with(m_start)) {
-                        break;
-                    }
-
-                    c.comment += 1;
+        let mut pos = 0;
+        let mut code = false;
+        let mut comment = false;
+        let mut doc = doc_block && !multi_stack.is_empty();
+
+        // the rest of a string literal is code, whatever it looks like
+        if let Some(quote) = open_string {
+            match string_end(line, 0, quote) {
+                StringEnd::At(end) => {
+                    open_string = None;
+                    pos = end;
+                    code = true;
+                }
+                StringEnd::Continues => {
+                    c.code += 1;
+                    continue 'line;
+                }
+                // a single line string continued with a backslash, but not closed
+                StringEnd::Unclosed => {
+                    open_string = None;
+                    c.code += 1;
                     continue 'line;
                 }
-            }
-
-            if multis.is_empty() {
-                c.code += 1;
-                continue 'line;
             }
         }
 
-        if multi_stack.is_empty() && !multis.iter().any(|(start, end)| line.contains(start) || line.contains(end)) {
+        // Most lines are plain code, don't look at them a character at a time.
+        if pos == 0 && multi_stack.is_empty() && outside.find(line.as_bytes()).is_none() {
             c.code += 1;
             continue 'line;
         }
 
-        let mut pos = 0;
-        let mut found_code = 0;
-        let line_len = line.len();
-        let contains_utf8 = (0..line_len).any(|i| !line.is_char_boundary(i));
-
-        'outer: while pos < line_len {
-            for multi in multis.iter() {
-                let (start, end) = multi;
-                let start_len = start.len();
-                let end_len   = end.len();
-
-                // TODO(cgag): this is almost ceratinly giving us incorrect results.  Say the
-                // first multi is the longest.  If we advance position because the final byte
-                // position of that multi hits unicode, we might have skipped over a perfectly
-                // valid comment start that was unaffected by the unicode.
-                if contains_utf8 {
-                    for i in pos..pos + min(max(start_len, end_len) + 1, line_len - pos) {
-                        if !line.is_char_boundary(i) {
-                            pos += 1;
-                            continue 'outer;
-                        }
-                    }
+        'scan: while pos < line.len() {
+            comment |= !multi_stack.is_empty();
+            // jump to the next byte that could start a marker
+            let markers = if multi_stack.is_empty() { &outside } else { &inside };
+            let skip = markers.find(&line.as_bytes()[pos..]).unwrap_or(line.len() - pos);
+            if skip > 0 {
+                if multi_stack.is_empty() && !code {
+                    code = !line[pos..pos + skip].trim_start().is_empty();
+                }
+                pos += skip;
+                continue 'scan;
+            }
+            let rest = &line[pos..];
+
+            if let Some(&(_, end)) = multi_stack.last() {
+                // a marker that both starts and ends a comment, like Python's ''', can't nest
+                let nested_start = multis.iter()
+                    .find(|(start, end)| syntax.nested && start != end && rest.starts_with(start));
+                if let Some(&multi) = nested_start {
+                    multi_stack.push(multi);
+                    pos += multi.0.len();
+                } else if rest.starts_with(end) {
+                    let _ = multi_stack.pop();
+                    pos += end.len();
+                } else {
+                    pos += next_char_len(rest);
                 }
+                continue 'scan;
+            }
 
-                if pos + start_len <= line_len && &line[pos..pos + start_len] == *start {
-                    pos += start_len;
-                    multi_stack.push(*multi);
-                    continue;
+            let after_space = line[..pos].ends_with(char::is_whitespace);
+            if pos == 0 || (trailing_comments && (after_space || !syntax.spaced_comments)) {
+                // TODO(cgag): this assumption that the multi-line comment is always the longer one
+                //             may well be a terrible one
+                let is_single = singles.iter().any(|single| rest.starts_with(single))
+                    && !multis.iter().any(|(start, _)| rest.starts_with(start));
+                if is_single {
+                    comment = true;
+                    doc |= is_doc(rest, docs);
+                    break 'scan;
                 }
+            }
 
-                if !multi_stack.is_empty() {
-                    let (_, mut end) = multi_stack.last().expect("stack last");
-                    if pos+end.len() <= line_len && &line[pos..pos+end.len()] == end {
-                        let _ = multi_stack.pop();
-                        pos += end.len();
+            if let Some(quote) = string_start(line, pos, quotes) {
+                // Python's ''' starts a comment at the beginning of a line, and a string
+                // after code: x = '''...
+                let is_comment = !code && multis.iter().any(|(start, _)| *start == quote.start);
+                if !is_comment {
+                    match string_end(line, pos + quote.start.len(), quote) {
+                        StringEnd::At(end) => {
+                            code = true;
+                            pos = end;
+                            continue 'scan;
+                        }
+                        StringEnd::Continues => {
+                            code = true;
+                            open_string = Some(quote);
+                            break 'scan;
+                        }
+                        // not a string after all, just a stray quote
+                        StringEnd::Unclosed => {}
                     }
-                } else if multi_stack.is_empty() && pos < line_len && !&line[pos..pos + 1].chars().next().expect("whitespace check").is_whitespace() {
-                    found_code += 1;
                 }
             }
-            pos += 1;
+
+            if let Some(&multi) = multis.iter().find(|(start, _)| rest.starts_with(start)) {
+                doc_block = is_doc(rest, docs);
+                doc |= doc_block;
+                multi_stack.push(multi);
+                comment = true;
+                pos += multi.0.len();
+                continue 'scan;
+            }
+
+            if !rest.starts_with(char::is_whitespace) {
+                code = true;
+            }
+            pos += next_char_len(rest);
         }
 
-        // TODO(cgag): can this ever be greater or was that just defensive coding
-        if found_code >= multis.len() {
+        if code {
+            // mixed lines are code until Count::with_mixed_lines says otherwise
             c.code += 1;
+            if comment {
+                c.mixed += 1;
+            }
         } else {
             c.comment += 1;
+            if doc {
+                c.doc += 1;
+            }
         }
     }
 
     c
 }
 
-fn check_shebang(path: &Path) -> Option<String> {
-    let mfile = File::open(path);
-    let mut file = match mfile {
-        Ok(file) => file,
-        Err(_) => {
-            // TODO(cgag): print warning
-            return None;
+/// Marked code lines are code, and every other line that isn't blank is a doc comment.
+/// The `\begin{code}` and `\end{code}` lines are LaTeX, so they're comments too.
+fn count_literate(literate: Literate, text: &str, lengths: &mut LineLengths) -> Count {
+    let mut c = Count::default();
+    let mut in_code_block = false;
+
+    for line in text_lines(text) {
+        c.lines += 1;
+        lengths.add(line);
+        let trimmed = line.trim();
+
+        let code = if in_code_block {
+            in_code_block = !trimmed.starts_with("\\end{code}");
+            in_code_block
+        } else if literate != Literate::Indented && trimmed.starts_with("\\begin{code}") {
+            in_code_block = true;
+            false
+        } else {
+            match literate {
+                // a bird track has to be in the first column
+                Literate::Bird => line.starts_with('>'),
+                Literate::Latex => false,
+                Literate::Indented => line.starts_with("    ") || line.starts_with('\t'),
+            }
+        };
+
+        // a bird track on its own is a blank line of code
+        if trimmed.is_empty() || (literate == Literate::Bird && line.trim_end() == ">") {
+            c.blank += 1;
+        } else if code {
+            c.code += 1;
+        } else {
+            c.comment += 1;
+            c.doc += 1;
         }
-    };
-    let mut bytes = vec![];
-    // TODO(cgag): don't need to read full file, just first line
-    file.read_to_end(&mut bytes).expect("nani?!");
-    let s = match std::str::from_utf8(&bytes) {
-        Ok(x) => x,
-        // TODO(cgag): warning
-        Err(_) => return None,
-    };
-
-    let first_line = s.lines().next();
-    if first_line.is_none() {
-        return None;
     }
+    c
+}
 
-    // credit to polyglot (ats line counter) for these shebangs
-    let ext = match first_line.expect("it's some, i'm sure of it") {
-        "#!python"
-      | "#!python2"
-      | "#!python3"
-      | "#!/bin/python"
-      | "#!/bin/python2"
-      | "#!/bin/python3"
-      | "#!/usr/bin/env python"
-      | "#!/usr/bin/env python2"
-      | "#!/usr/bin/env python3" => "py",
-
-        "#!/usr/bin/env bash"
-      | "#!/usr/bin/env sh"
-      | "#!/bin/bash"
-      | "#!/bin/sh" => "sh",
-
-
-        "#!/usr/bin/env perl"
-      | "#!/usr/bin/env perl6"
-      | "#!/bin/perl"
-      | "#!/bin/perl6"
-      | "#!/usr/bin/perl" => "pl",
-
-        "#!/usr/bin/env stack"
-      | "#!/usr/bin/env runhaskell" => "hs",
-
-        "#!/usr/bin/csh"      => "csh",
-        "#!/usr/bin/env node" => "js",
-        "#!/usr/bin/ruby"     => "rb",
-        "#!/usr/bin/env ruby" => "rb",
-
-        _ => return None
-    };
-
-    Some(String::from(ext))
+/// The first bytes of a set of markers, so the line counter can jump from one place a
+/// marker could start to the next instead of trying every marker at every character.
+struct MarkerBytes {
+    bytes: SmallVec<[u8; 8]>,
+    table: [bool; 256],
+}
+
+impl MarkerBytes {
+    fn new<'a, I: Iterator<Item = &'a str>>(markers: I) -> MarkerBytes {
+        let mut m = MarkerBytes { bytes: SmallVec::new(), table: [false; 256] };
+        for b in markers.filter_map(|marker| marker.bytes().next()) {
+            if !m.table[b as usize] {
+                m.table[b as usize] = true;
+                m.bytes.push(b);
+            }
+        }
+        m
+    }
+
+    /// The offset of the first byte of `haystack` a marker could start at. Marker starts
+    /// are never UTF-8 continuation bytes, so it's a char boundary.
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        match *self.bytes.as_slice() {
+            []        => None,
+            [a]       => memchr(a, haystack),
+            [a, b]    => memchr2(a, b, haystack),
+            [a, b, c] => memchr3(a, b, c, haystack),
+            _         => haystack.iter().position(|&b| self.table[b as usize]),
+        }
+    }
+}
+
+fn next_char_len(s: &str) -> usize {
+    s.chars().next().map_or(1, char::len_utf8)
+}
+
+/// Whether a comment starting at the beginning of `comment` is documentation.
+fn is_doc(comment: &str, doc_comments: &[&str]) -> bool {
+    doc_comments.iter().any(|marker| {
+        comment.starts_with(marker)
+            && !comment[marker.len()..].starts_with(|c: char| !c.is_whitespace() && marker.contains(c))
+    })
+}
+
+/// The longest string start marker at byte `pos` of `line`.
+fn string_start<'q, 'a>(line: &str, pos: usize, quotes: &'q [Quote<'a>]) -> Option<&'q Quote<'a>> {
+    let rest = &line.as_bytes()[pos..];
+    quotes.iter()
+        .filter(|q| rest.starts_with(q.start.as_bytes()))
+        .max_by_key(|q| q.start.len())
+}
+
+enum StringEnd {
+    /// The position just past the end marker.
+    At(usize),
+    /// The string goes on to the next line: it's multiline, or the line ends in an escape.
+    Continues,
+    /// A single line string without an end.
+    Unclosed,
+}
+
+/// Finds the end of a string whose contents begin at byte `pos` of `line`.
+fn string_end(line: &str, mut pos: usize, quote: &Quote) -> StringEnd {
+    // works on bytes, since an escape can be followed by a multibyte character
+    let bytes = line.as_bytes();
+    let end_byte = quote.end.as_bytes()[0];
+    while pos < bytes.len() {
+        // skip ahead to the next byte that could start an escape or the end
+        let next = match quote.escape {
+            Some(escape) => memchr2(escape.as_bytes()[0], end_byte, &bytes[pos..]),
+            None         => memchr(end_byte, &bytes[pos..]),
+        };
+        match next {
+            Some(i) => pos += i,
+            None    => break,
+        }
+        let rest = &bytes[pos..];
+        if let Some(escape) = quote.escape {
+            if rest.starts_with(escape.as_bytes()) {
+                if pos + escape.len() == bytes.len() {
+                    return StringEnd::Continues;
+                }
+                pos += escape.len() + 1;
+                continue;
+            }
+        }
+        if rest.starts_with(quote.end.as_bytes()) {
+            return StringEnd::At(pos + quote.end.len());
+        }
+        pos += 1;
+    }
+    if quote.multiline { StringEnd::Continues } else { StringEnd::Unclosed }
+}
+
+/// The program a shebang line runs: "#!/usr/bin/env python3 -u" -> "python3",
+/// "#! /usr/bin/env -S deno run" -> "deno".
+fn shebang_interpreter(line: &str) -> Option<&str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let program = words.next()?.rsplit('/').next()?;
+    if program != "env" {
+        return Some(program);
+    }
+    // skip env's flags, the arguments of -u and -C, and any VAR=value assignments
+    while let Some(word) = words.next() {
+        match word {
+            "-u" | "--unset" | "-C" | "--chdir" => { words.next(); }
+            // -S splits the rest of the line into arguments, so "-Spython3 -u" runs python3
+            w if w.starts_with("-S") && w.len() > 2 => return w[2..].rsplit('/').next(),
+            w if w.starts_with('-') || w.contains('=') => {}
+            w => return w.rsplit('/').next(),
+        }
+    }
+    None
 }
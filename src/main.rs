@@ -7,13 +7,26 @@ extern crate num_cpus;
 extern crate regex;
 extern crate ignore;
 extern crate edit_distance;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+mod gitattributes;
+mod output;
 
 use clap::{Arg, App, AppSettings};
 use ignore::WalkBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
 
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::env;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
+use std::time::Instant;
 use std::str::FromStr;
 
 use deque::{Stealer, Stolen};
@@ -21,14 +34,29 @@ use regex::Regex;
 use edit_distance::edit_distance as distance;
 
 use loc::*;
+use gitattributes::GitAttributes;
+use output::{Columns, FileColumns};
+
+/// Language definitions loaded from the home and current directories, see --languages-file.
+const LANGUAGES_FILE: &str = ".loc-languages.toml";
+
+/// Files and directories not to count, in .gitignore syntax. Read in every directory, like
+/// .gitignore files are.
+const LOCIGNORE_FILE: &str = ".locignore";
 
 enum Work {
-    File(String),
+    /// A file, and its language if .gitattributes set it.
+    File(String, Option<Lang>),
     Quit,
 }
 
 struct Worker {
     chan: Stealer<Work>,
+    languages: Arc<LanguageRegistry>,
+    mixed_lines: MixedLines,
+    embedded: Option<EmbeddedMode>,
+    encoding: Encoding,
+    long_line: u32,
 }
 
 #[derive(Clone)]
@@ -36,31 +64,120 @@ struct FileCount {
     path: String,
     lang: Lang,
     count: Count,
+    /// The languages embedded in the file with --embedded nest. They're included in `count`.
+    embedded: Vec<(Lang, Count)>,
+    /// The languages embedded in the file with --embedded split. They're counted in their
+    /// own languages' rows instead of `count`, which only has the file's own language.
+    split: Vec<(Lang, Count)>,
+    line_endings: LineEndings,
+    /// The lengths of all the file's lines, embedded languages included.
+    lengths: LineLengths,
+    /// Minified JavaScript or CSS, see `is_minified`.
+    minified: bool,
+    /// Written by a tool, see `is_generated`. Minified files are.
+    generated: bool,
+}
+
+/// A file we found but couldn't count, and why.
+struct Skipped {
+    path: String,
+    err: CountError,
 }
 
 // This concurrency pattern ripped directly from ripgrep
 impl Worker {
-    fn run(self) -> Vec<FileCount> {
+    fn run(self) -> (Vec<FileCount>, Vec<Skipped>) {
         let mut v: Vec<FileCount> = vec![];
+        let mut skipped: Vec<Skipped> = vec![];
         loop {
             match self.chan.steal() {
                 // What causes these?
                 Stolen::Empty | Stolen::Abort => continue,
                 Stolen::Data(Work::Quit) => break,
-                Stolen::Data(Work::File(path)) => {
-                    let lang = lang_from_ext(&path);
-                    if lang != Lang::Unrecognized {
-                        let count = count(&path);
-                        v.push(FileCount {
-                            lang,
-                            path,
-                            count,
-                        });
+                Stolen::Data(Work::File(path, lang)) => {
+                    match self.count(lang, &path) {
+                        Ok(count) => v.extend(count),
+                        Err(err) => skipped.push(Skipped { path, err }),
                     }
                 }
             };
         }
-        v
+        (v, skipped)
+    }
+
+    /// Counts the file at `path`, working out its language unless .gitattributes already
+    /// did. Files in languages we don't know count as nothing.
+    fn count(&self, lang: Option<Lang>, path: &str) -> Result<Option<FileCount>, CountError> {
+        let (lang, bytes) = match lang {
+            Some(lang) => (lang, read_source(File::open(path)?)?),
+            None => self.languages.read_detected(path)?,
+        };
+        if lang == Lang::Unrecognized {
+            return Ok(None);
+        }
+        let text = decode_source(&bytes, self.encoding)?;
+        let line_endings = loc::line_endings(text.as_bytes());
+        let file = |count, embedded, split, lengths| {
+            let minified = is_minified(lang, &lengths);
+            FileCount {
+                lang,
+                path: String::from(path),
+                count,
+                embedded,
+                split,
+                line_endings,
+                lengths,
+                minified,
+                generated: is_generated(path, lang, &text, &lengths),
+            }
+        };
+
+        let mode = match self.embedded {
+            None => {
+                let (count, lengths) = self.languages.try_count_text_lengths(lang, &text, self.long_line)?;
+                return Ok(Some(file(count.with_mixed_lines(self.mixed_lines), vec![], vec![], lengths)));
+            }
+            Some(mode) => mode,
+        };
+
+        let counts = self.languages.try_count_text_embedded(lang, &text, self.long_line)?;
+        let lengths = counts.lengths;
+        let host = counts.host.with_mixed_lines(self.mixed_lines);
+        let embedded: Vec<(Lang, Count)> = counts.embedded.into_iter()
+            .map(|(lang, count)| (lang, count.with_mixed_lines(self.mixed_lines)))
+            .collect();
+        Ok(Some(match mode {
+            // every language is counted in its own row, but the file is only one file
+            EmbeddedMode::Split => file(host, vec![], embedded, lengths),
+            EmbeddedMode::Nest => {
+                let mut count = host;
+                for (_, c) in &embedded {
+                    count.merge(c);
+                }
+                file(count, embedded, vec![], lengths)
+            }
+        }))
+    }
+}
+
+/// What --embedded does with the languages embedded in HTML, Vue, Svelte, Razor, ERB,
+/// Markdown and reStructuredText files.
+#[derive(Clone, Copy, PartialEq)]
+enum EmbeddedMode {
+    /// Counted as their own languages.
+    Split,
+    /// Counted as the host language, and shown below it.
+    Nest,
+}
+
+impl FromStr for EmbeddedMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<EmbeddedMode, Self::Err> {
+        match s {
+            "split" => Ok(EmbeddedMode::Split),
+            "nest"  => Ok(EmbeddedMode::Nest),
+            _ => Err(format!("unknown --embedded mode '{}'", s)),
+        }
     }
 }
 
@@ -99,15 +216,51 @@ impl FromStr for Sort {
     }
 }
 
+#[derive(PartialEq)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+    Tsv,
+    ClocJson,
+    ClocYaml,
+    ClocXml,
+    Markdown,
+    Html,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Format, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json"  => Ok(Format::Json),
+            "csv"   => Ok(Format::Csv),
+            "tsv"   => Ok(Format::Tsv),
+            "cloc-json" => Ok(Format::ClocJson),
+            "cloc-yaml" => Ok(Format::ClocYaml),
+            "cloc-xml"  => Ok(Format::ClocXml),
+            "markdown"  => Ok(Format::Markdown),
+            "html"      => Ok(Format::Html),
+            _ => Err(format!("unknown format '{}'", s)),
+        }
+    }
+}
+
 // TODO(cgag): tune smallvec array sizes
 // TODO(cgag): try smallstring
 // TODO(cgag): more tests for nested comments
 fn main() {
+    let start = Instant::now();
     let matches = App::new("loc")
         .global_settings(&[AppSettings::ColoredHelp])
         .version(crate_version!())
         .author("Curtis Gagliardi <curtis@curtis.io>")
         .about("counts things quickly hopefully")
+        .after_help("A Vim or Emacs modeline only names a file's language when its extension doesn't: \
+                     it has none, or a generic or ambiguous one, or the modeline names a close \
+                     relative (C and C++, sh and zsh). A linguist-language attribute in \
+                     .gitattributes overrides any extension.")
         .arg(Arg::with_name("exclude")
             .required(false)
             .multiple(true)
@@ -122,24 +275,143 @@ fn main() {
             .value_name("REGEX")
             .takes_value(true)
             .help("Rust regex matching files to include. Anything not matched will be excluded"))
+        .arg(Arg::with_name("glob")
+            .required(false)
+            .multiple(true)
+            .number_of_values(1)
+            .long("glob")
+            .value_name("GLOB")
+            .takes_value(true)
+            .help("Only count files matching this glob, in .gitignore syntax. Start it with ! to \
+                   exclude the files it matches instead. Can be given more than once, later globs win"))
+        .arg(Arg::with_name("exclude-glob")
+            .required(false)
+            .multiple(true)
+            .number_of_values(1)
+            .long("exclude-glob")
+            .value_name("GLOB")
+            .takes_value(true)
+            .help("Don't count files or directories matching this glob, in .gitignore syntax, \
+                   like --glob '!GLOB'. Can be given more than once"))
+        .arg(Arg::with_name("languages-file")
+            .required(false)
+            .multiple(true)
+            .long("languages-file")
+            .value_name("FILE")
+            .takes_value(true)
+            .number_of_values(1)
+            .help("TOML or JSON file of extra language definitions. ~/.loc-languages.toml and \
+                   ./.loc-languages.toml are loaded automatically if they exist"))
+        .arg(Arg::with_name("list-languages")
+             .required(false)
+             .long("list-languages")
+             .takes_value(false)
+             .help("List every language loc knows about, how its files are recognized, \
+                    and its comment syntax"))
         .arg(Arg::with_name("files")
              .required(false)
              .long("files")
              .takes_value(false)
              .help("Show stats for individual files"))
+        .arg(Arg::with_name("generated")
+             .required(false)
+             .long("generated")
+             .takes_value(false)
+             .help("Count generated files: lockfiles, protobuf stubs, minified JavaScript and CSS, \
+                    and files marked 'Code generated ... DO NOT EDIT' or '@generated'"))
+        .arg(Arg::with_name("vendored")
+             .required(false)
+             .long("vendored")
+             .takes_value(false)
+             .help("Count the files in vendor, node_modules, third_party and other directories \
+                    of third party code"))
+        .arg(Arg::with_name("line-endings")
+             .required(false)
+             .long("line-endings")
+             .takes_value(false)
+             .help("With --files, add a column with each file's line endings: lf, crlf, cr, \
+                    mixed, or none for a file without any"))
+        .arg(Arg::with_name("line-lengths")
+             .required(false)
+             .long("line-lengths")
+             .takes_value(false)
+             .help("With --files, add columns with the length of each file's longest line, \
+                    the mean length of its lines, and how many are longer than --long-line"))
+        .arg(Arg::with_name("long-line")
+             .required(false)
+             .long("long-line")
+             .value_name("N")
+             .takes_value(true)
+             .default_value("120")
+             .help("Lines longer than this many characters are long, see --line-lengths"))
+        .arg(Arg::with_name("doc")
+             .required(false)
+             .long("doc")
+             .takes_value(false)
+             .help("Add a column with how many of the comment lines are documentation, \
+                    like /// or /** */"))
+        .arg(Arg::with_name("mixed")
+             .required(false)
+             .long("mixed")
+             .value_name("AS")
+             .takes_value(true)
+             .possible_values(&["code", "comment", "both"])
+             .default_value("code")
+             .help("Count lines with both code and a comment as code, comment or both, \
+                    and add a column with how many there are"))
+        .arg(Arg::with_name("embedded")
+             .required(false)
+             .long("embedded")
+             .value_name("MODE")
+             .takes_value(true)
+             .possible_values(&["split", "nest"])
+             .help("Count the <script> and <style> blocks of HTML, Vue and Svelte files, \
+                    the code in Razor and ERB templates, and the code blocks in Markdown and \
+                    reStructuredText as their own languages, and Markdown and reStructuredText \
+                    prose as documentation. 'split' counts them with the other files in those \
+                    languages, 'nest' shows them under the file's language"))
+        .arg(Arg::with_name("encoding")
+             .required(false)
+             .long("encoding")
+             .value_name("ENCODING")
+             .takes_value(true)
+             .default_value("auto")
+             .help("Read every file as utf-8, utf-16le, utf-16be or latin-1. 'auto' goes by \
+                    the byte order mark, and reads files that aren't UTF-8 as UTF-16 or \
+                    Latin-1. With utf-8, files that aren't valid UTF-8 are skipped"))
         .arg(Arg::with_name("sort")
             .required(false)
             .long("sort")
             .value_name("COLUMN")
             .takes_value(true)
             .help("Column to sort by"))
+        .arg(Arg::with_name("format")
+            .required(false)
+            .long("format")
+            .value_name("FORMAT")
+            .takes_value(true)
+            .possible_values(&["table", "json", "csv", "tsv", "cloc-json", "cloc-yaml", "cloc-xml",
+                              "markdown", "html"])
+            .default_value("table")
+            .help("Output format. The cloc-* formats match the reports of cloc's --json, --yaml and --xml"))
+        .arg(Arg::with_name("no-header")
+             .required(false)
+             .long("no-header")
+             .takes_value(false)
+             .help("Don't print a header row with --format csv or tsv"))
+        .arg(Arg::with_name("strict")
+             .required(false)
+             .long("strict")
+             .takes_value(false)
+             .help("Exit with an error if any file couldn't be counted. Binary files are only \
+                    reported on stderr and don't make this fail"))
         .arg(Arg::with_name("unrestricted")
              .required(false)
              .multiple(true)
              .long("unrestricted")
              .short("u")
              .takes_value(false)
-             .help("A single -u won't respect .gitignore (etc.) files. Two -u flags will additionally count hidden files and directories."))
+             .help("A single -u won't respect .gitignore (etc.) files, but still respects .locignore files. Two -u flags will additionally ignore .locignore files and count hidden files and directories."))
         .arg(Arg::with_name("target")
             .multiple(true)
             .help("File or directory to count (multiple arguments accepted)"))
@@ -162,7 +434,7 @@ fn main() {
                 }
                 println!(" Hint: legal values are Code, Comment, Blank, Lines, Language, \
                           and Files");
-                return
+                std::process::exit(1);
             },
         },
         // Default to sorting by lines of code
@@ -170,17 +442,46 @@ fn main() {
     };
 
     let by_file: bool = matches.is_present("files");
+    let format = value_t!(matches, "format", Format).unwrap_or_else(|e| e.exit());
+    let header: bool = !matches.is_present("no-header");
+    let mixed_lines = value_t!(matches, "mixed", MixedLines).unwrap_or_else(|e| e.exit());
+    let columns = Columns {
+        doc: matches.is_present("doc"),
+        mixed: matches.occurrences_of("mixed") > 0,
+    };
+    let embedded = if matches.is_present("embedded") {
+        Some(value_t!(matches, "embedded", EmbeddedMode).unwrap_or_else(|e| e.exit()))
+    } else {
+        None
+    };
+    let encoding = value_t!(matches, "encoding", Encoding).unwrap_or_else(|e| e.exit());
+    let strict: bool = matches.is_present("strict");
+    let file_columns = FileColumns {
+        line_endings: matches.is_present("line-endings"),
+        line_lengths: matches.is_present("line-lengths"),
+    };
+    let long_line = value_t!(matches, "long-line", u32).unwrap_or_else(|e| e.exit());
+    let include_generated: bool = matches.is_present("generated");
+    let include_vendored: bool = matches.is_present("vendored");
 
     if by_file && (sort == Sort::Language || sort == Sort::Files) {
         println!("Error: cannot sort by Language or Files when --files is present");
-        return
+        std::process::exit(1);
+    }
+
+    let tabular = format == Format::Table || format == Format::Csv || format == Format::Tsv;
+    if (file_columns.line_endings || file_columns.line_lengths) && !(by_file && tabular) {
+        println!("Error: --line-endings and --line-lengths only work with --files and \
+                  --format table, csv or tsv, --format json always has them");
+        std::process::exit(1);
     }
 
-    let (use_ignore, ignore_hidden) = match matches.occurrences_of("unrestricted") {
-        0 => (true,  true),
-        1 => (false, true),
-        2 => (false, false),
-        _ => (false, false),
+    // .locignore only lists what loc shouldn't count, so a single -u keeps it
+    let (use_ignore, use_locignore, ignore_hidden) = match matches.occurrences_of("unrestricted") {
+        0 => (true,  true,  true),
+        1 => (false, true,  true),
+        2 => (false, false, false),
+        _ => (false, false, false),
     };
     let exclude_regex = match matches.values_of("exclude") {
         Some(regex_strs) => {
@@ -195,6 +496,10 @@ fn main() {
         }
         None => None,
     };
+    // --exclude-glob X is --glob !X, after the --globs so it wins over them
+    let mut globs: Vec<String> = matches.values_of("glob").into_iter().flatten().map(String::from).collect();
+    globs.extend(matches.values_of("exclude-glob").into_iter().flatten().map(|glob| format!("!{}", glob)));
+
     let include_regex = match matches.values_of("include") {
         Some(regex_strs) => {
             let combined_regex = regex_strs.map(|r| format!("({})", r)).collect::<Vec<String>>().join("|");
@@ -209,21 +514,79 @@ fn main() {
         None => None,
     };
 
+    let mut languages = LanguageRegistry::new();
+    let mut language_files = vec![];
+    if let Some(home) = env::var_os("HOME") {
+        language_files.push(PathBuf::from(home).join(LANGUAGES_FILE));
+    }
+    language_files.push(PathBuf::from(LANGUAGES_FILE));
+    language_files.retain(|path| path.is_file());
+    if let Some(paths) = matches.values_of("languages-file") {
+        language_files.extend(paths.map(PathBuf::from));
+    }
+    for path in language_files {
+        if let Err(e) = languages.load_file(&path) {
+            println!("Error loading language definitions from {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+
+    if matches.is_present("list-languages") {
+        match format {
+            Format::Table => output::print_languages_table(&languages),
+            Format::Json  => output::print_languages_json(&languages),
+            _ => {
+                println!("Error: --list-languages only supports --format table or json");
+                std::process::exit(1);
+            }
+        }
+        return
+    }
+
+    let languages = Arc::new(languages);
+
     let threads = num_cpus::get();
     let mut workers = vec![];
     let (workq, stealer) = deque::new();
     for _ in 0..threads {
-        let worker = Worker { chan: stealer.clone() };
+        let worker = Worker {
+            chan: stealer.clone(),
+            languages: languages.clone(),
+            mixed_lines,
+            embedded,
+            encoding,
+            long_line,
+        };
         workers.push(thread::spawn(|| worker.run()));
     }
 
+    let vendored_dirs = Arc::new(AtomicUsize::new(0));
     for target in targets {
         // TODO(cgag): use WalkParallel?
-        let walker = WalkBuilder::new(target).ignore(use_ignore)
-                                             .git_ignore(use_ignore)
-                                             .git_exclude(use_ignore)
-                                             .hidden(ignore_hidden)
-                                             .build();
+        let mut builder = WalkBuilder::new(target);
+        builder.ignore(use_ignore)
+               .git_ignore(use_ignore)
+               .git_exclude(use_ignore)
+               .hidden(ignore_hidden)
+               .overrides(overrides(target, &globs));
+        if use_locignore {
+            builder.add_custom_ignore_filename(LOCIGNORE_FILE);
+        }
+        if !include_vendored {
+            let vendored_dirs = vendored_dirs.clone();
+            builder.filter_entry(move |entry| {
+                // a target is counted even if it's vendored, it was asked for
+                let vendored = entry.depth() > 0
+                    && entry.file_type().is_some_and(|t| t.is_dir())
+                    && entry.file_name().to_str().is_some_and(is_vendored_dir);
+                if vendored {
+                    vendored_dirs.fetch_add(1, Ordering::Relaxed);
+                }
+                !vendored
+            });
+        }
+        let walker = builder.build();
+        let mut attributes = GitAttributes::new(target);
         let files = walker
             .filter_map(Result::ok)
             .filter(|entry| entry.file_type().expect("no filetype").is_file())
@@ -238,7 +601,8 @@ fn main() {
             });
 
         for path in files {
-            workq.push(Work::File(path));
+            let lang = attributes.language(&languages, Path::new(&path));
+            workq.push(Work::File(path, lang));
         }
     }
 
@@ -247,10 +611,16 @@ fn main() {
     }
 
     let mut filecounts: Vec<FileCount> = Vec::new();
+    let mut skipped: Vec<Skipped> = Vec::new();
     for worker in workers {
-        filecounts.extend(worker.join().unwrap().iter().cloned())
+        let (counted, not_counted) = worker.join().unwrap();
+        filecounts.extend(counted);
+        skipped.extend(not_counted);
     }
 
+    let generated = if include_generated { 0 } else { filecounts.iter().filter(|fc| fc.generated).count() };
+    filecounts.retain(|fc| include_generated || !fc.generated);
+
     // TODO(cgag): use insecure hashmaps or something
     let mut by_lang: HashMap<Lang, Vec<FileCount>> = HashMap::new();
     for fc in filecounts {
@@ -262,37 +632,9 @@ fn main() {
         };
     }
 
-    let linesep = str_repeat("-", 80);
-
     if by_file {
-        // print breakdown for each individual file
-        println!("{}", linesep);
-        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-                 "Language",
-                 "Files",
-                 "Lines",
-                 "Blank",
-                 "Comment",
-                 "Code");
-        println!("{}", linesep);
-
-        // TODO(cgag): do the summing first, so we can do additional sorting
-        // by totals.
-        for (lang, mut filecounts) in by_lang {
-            let mut total = Count::default();
-            for fc in &filecounts {
-                total.merge(&fc.count);
-            }
-
-            println!("{}", linesep);
-            println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-                     lang,
-                     filecounts.len(),
-                     total.lines,
-                     total.blank,
-                     total.comment,
-                     total.code);
-
+        for filecounts in by_lang.values_mut() {
+            #[allow(clippy::unnecessary_sort_by)]
             match sort {
                 Sort::Code    => filecounts.sort_by(|fc1, fc2| fc2.count.code.cmp(&fc1.count.code)),
                 Sort::Comment => filecounts.sort_by(|fc1, fc2| fc2.count.comment.cmp(&fc1.count.comment)),
@@ -303,45 +645,107 @@ fn main() {
                 Sort::Language | Sort::Files => panic!("Sorting by language or files \
                                                         when using the --sort flag"),
             }
+        }
+    }
 
-            println!("{}", linesep);
-            for fc in filecounts {
-                println!("|{0: <25} {1: >12} {2: >12} {3: >12} {4: >12}",
-                         last_n_chars(&fc.path, 25),
-                         fc.count.lines,
-                         fc.count.blank,
-                         fc.count.comment,
-                         fc.count.code);
-            }
+    // with --embedded split, embedded languages add to their own rows but not their files,
+    // so a language only ever embedded has a row with no files
+    let mut split: HashMap<Lang, Count> = HashMap::new();
+    for fc in by_lang.values().flatten() {
+        for &(lang, ref count) in &fc.split {
+            split.entry(lang).or_default().merge(count);
         }
-    } else {
-        // print summary by language
-        let mut lang_totals: HashMap<&Lang, LangTotal> = HashMap::new();
-        for (lang, filecounts) in &by_lang {
-            let mut lang_total = Count::default();
-            for fc in filecounts {
-                lang_total.merge(&fc.count);
-            }
-            lang_totals.insert(lang,
-                               LangTotal {
-                                   files: filecounts.len() as u32,
-                                   count: lang_total,
-                               });
+    }
+    for &lang in split.keys() {
+        by_lang.entry(lang).or_default();
+    }
+
+    let mut lang_totals: HashMap<&Lang, LangTotal> = HashMap::new();
+    for (lang, filecounts) in &by_lang {
+        let mut lang_total = split.get(lang).cloned().unwrap_or_default();
+        for fc in filecounts {
+            lang_total.merge(&fc.count);
         }
+        lang_totals.insert(lang,
+                           LangTotal {
+                               files: filecounts.len() as u32,
+                               count: lang_total,
+                           });
+    }
+
+    let mut totals_by_lang = lang_totals.iter().collect::<Vec<(&&Lang, &LangTotal)>>();
+    // descending, and Language can't be a key without cloning the name
+    #[allow(clippy::unnecessary_sort_by)]
+    match sort {
+        Sort::Language => totals_by_lang.sort_by(|&(l1, _), &(l2, _)| l1.to_s().cmp(l2.to_s())),
+        Sort::Files    => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.files.cmp(&c1.files)),
+        Sort::Code     => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.code.cmp(&c1.count.code)),
+        Sort::Comment  => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.comment.cmp(&c1.count.comment)),
+        Sort::Blank    => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.blank.cmp(&c1.count.blank)),
+        Sort::Lines    => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.lines.cmp(&c1.count.lines)),
+    }
 
-        let mut totals_by_lang = lang_totals.iter().collect::<Vec<(&&Lang, &LangTotal)>>();
-        match sort {
-            Sort::Language => totals_by_lang.sort_by(|&(l1, _), &(l2, _)| l1.to_s().cmp(l2.to_s())),
-            Sort::Files    => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.files.cmp(&c1.files)),
-            Sort::Code     => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.code.cmp(&c1.count.code)),
-            Sort::Comment  => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.comment.cmp(&c1.count.comment)),
-            Sort::Blank    => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.blank.cmp(&c1.count.blank)),
-            Sort::Lines    => totals_by_lang.sort_by(|&(_, c1), &(_, c2)| c2.count.lines.cmp(&c1.count.lines)),
+    match format {
+        Format::Table => {
+            let extra = columns.headers().len() - 4 + file_columns.headers().len();
+            let linesep = str_repeat("-", 80 + 13 * extra);
+            if by_file {
+                print_files_by_lang(&linesep, &totals_by_lang, &by_lang, columns, file_columns);
+            } else {
+                print_totals_by_lang(&linesep, &totals_by_lang, &by_lang, columns);
+            }
         }
+        Format::Json => output::print_json(&totals_by_lang, &by_lang, by_file),
+        Format::Csv  => output::print_delimited(&totals_by_lang, &by_lang, by_file, columns, file_columns, ',', header),
+        Format::Tsv  => output::print_delimited(&totals_by_lang, &by_lang, by_file, columns, file_columns, '\t', header),
+        Format::ClocJson => output::print_cloc_json(&totals_by_lang, &by_lang, by_file, start.elapsed()),
+        Format::ClocYaml => output::print_cloc_yaml(&totals_by_lang, &by_lang, by_file, start.elapsed()),
+        Format::ClocXml  => output::print_cloc_xml(&totals_by_lang, &by_lang, by_file, start.elapsed()),
+        Format::Markdown => output::print_markdown(&totals_by_lang, &by_lang, by_file, columns),
+        Format::Html     => output::print_html(&totals_by_lang, &by_lang, by_file, columns),
+    }
+
+    // binary files with a source code extension are common (object files, images), and
+    // not worth listing
+    let binary = skipped.len();
+    skipped.retain(|s| !matches!(s.err, CountError::Binary));
+    let binary = binary - skipped.len();
+    if binary > 0 {
+        eprintln!("Binary files skipped: {}", binary);
+    }
+    if generated > 0 {
+        eprintln!("Generated files skipped: {} (count them with --generated)", generated);
+    }
+    let vendored_dirs = vendored_dirs.load(Ordering::Relaxed);
+    if vendored_dirs > 0 {
+        eprintln!("Vendored directories skipped: {} (count them with --vendored)", vendored_dirs);
+    }
 
-        print_totals_by_lang(&linesep, &totals_by_lang);
+    if !skipped.is_empty() {
+        skipped.sort_by(|s1, s2| s1.path.cmp(&s2.path));
+        eprintln!("Skipped {} file(s) that couldn't be counted:", skipped.len());
+        for s in &skipped {
+            eprintln!("  {}: {}", s.path, s.err);
+        }
+        if strict {
+            std::process::exit(1);
+        }
     }
+}
 
+/// --glob and --exclude-glob, matched relative to `target` like a .gitignore in it would be.
+fn overrides(target: &str, globs: &[String]) -> Override {
+    let mut builder = OverrideBuilder::new(target);
+    for glob in globs {
+        if let Err(e) = builder.add(glob) {
+            println!("Error processing glob '{}': {}", glob, e);
+            std::process::exit(1);
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        println!("Error processing globs: {}", e);
+        std::process::exit(1);
+    })
 }
 
 // TODO(cgag): i think this is in the stdlib
@@ -354,49 +758,104 @@ fn last_n_chars(s: &str, n: usize) -> String {
 
 // TODO(cgag): i think this is in the stdlib
 fn str_repeat(s: &str, n: usize) -> String {
-    std::iter::repeat(s).take(n).collect::<Vec<_>>().join("")
+    s.repeat(n)
 }
 
-fn print_totals_by_lang(linesep: &str, totals_by_lang: &[(&&Lang, &LangTotal)]) {
+fn count_columns(fields: Vec<String>) -> String {
+    fields.iter().map(|field| format!("{: >12}", field)).collect::<Vec<_>>().join(" ")
+}
+
+fn print_totals_by_lang(linesep: &str,
+                        totals_by_lang: &[(&&Lang, &LangTotal)],
+                        by_lang: &HashMap<Lang, Vec<FileCount>>,
+                        columns: Columns) {
     println!("{}", linesep);
-    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-             "Language",
-             "Files",
-             "Lines",
-             "Blank",
-             "Comment",
-             "Code");
+    println!(" {0: <17} {1: >8} {2}", "Language", "Files", count_columns(columns.headers()));
     println!("{}", linesep);
 
     for &(lang, total) in totals_by_lang {
-        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-                 lang,
-                 total.files,
-                 total.count.lines,
-                 total.count.blank,
-                 total.count.comment,
-                 total.count.code);
+        println!(" {0: <17} {1: >8} {2}", lang, total.files, count_columns(columns.fields(&total.count)));
+        print_embedded(&by_lang[*lang], columns);
+    }
+
+    let totals = grand_total(totals_by_lang);
+
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2}", "Total", totals.files, count_columns(columns.fields(&totals.count)));
+    println!("{}", linesep);
+}
+
+fn print_files_by_lang(linesep: &str,
+                       totals_by_lang: &[(&&Lang, &LangTotal)],
+                       by_lang: &HashMap<Lang, Vec<FileCount>>,
+                       columns: Columns,
+                       file_columns: FileColumns) {
+    // print breakdown for each individual file
+    println!("{}", linesep);
+    let mut headers = columns.headers();
+    headers.extend(file_columns.headers());
+    println!(" {0: <17} {1: >8} {2}", "Language", "Files", count_columns(headers));
+    println!("{}", linesep);
+
+    // with --embedded split, the files a language is embedded in are listed under it too,
+    // with the lines they have in it
+    let mut split: HashMap<Lang, Vec<(&FileCount, &Count)>> = HashMap::new();
+    for &(lang, _) in totals_by_lang {
+        for fc in &by_lang[*lang] {
+            for &(lang, ref count) in &fc.split {
+                split.entry(lang).or_default().push((fc, count));
+            }
+        }
+    }
+
+    for &(lang, total) in totals_by_lang {
+        println!("{}", linesep);
+        println!(" {0: <17} {1: >8} {2}", lang, total.files, count_columns(columns.fields(&total.count)));
+        print_embedded(&by_lang[*lang], columns);
+
+        println!("{}", linesep);
+        let own = by_lang[*lang].iter().map(|fc| (fc, &fc.count));
+        for (fc, count) in own.chain(split.get(*lang).into_iter().flatten().cloned()) {
+            let mut fields = columns.fields(count);
+            fields.extend(file_columns.fields(fc));
+            println!("|{0: <25} {1}", last_n_chars(&fc.path, 25), count_columns(fields));
+        }
     }
+}
 
+/// The rows for the languages embedded in one language's files, with --embedded nest.
+fn print_embedded(filecounts: &[FileCount], columns: Columns) {
+    for (lang, total) in embedded_totals(filecounts) {
+        println!(" |- {0: <14} {1: >8} {2}", lang, total.files, count_columns(columns.fields(&total.count)));
+    }
+}
+
+/// Totals for each language embedded in `filecounts`, most code first. `files` is how
+/// many of the files embed it.
+fn embedded_totals(filecounts: &[FileCount]) -> Vec<(Lang, LangTotal)> {
+    let mut totals: HashMap<Lang, LangTotal> = HashMap::new();
+    for fc in filecounts {
+        for &(lang, ref count) in &fc.embedded {
+            let total = totals.entry(lang).or_insert(LangTotal { files: 0, count: Count::default() });
+            total.files += 1;
+            total.count.merge(count);
+        }
+    }
+    let mut totals: Vec<(Lang, LangTotal)> = totals.into_iter().collect();
+    totals.sort_by(|&(l1, ref t1), &(l2, ref t2)| {
+        t2.count.code.cmp(&t1.count.code).then_with(|| l1.to_s().cmp(l2.to_s()))
+    });
+    totals
+}
+
+fn grand_total(totals_by_lang: &[(&&Lang, &LangTotal)]) -> LangTotal {
     let mut totals = LangTotal {
         files: 0,
         count: Count::default(),
     };
     for &(_, total) in totals_by_lang {
-        totals.files         += total.files;
-        totals.count.code    += total.count.code;
-        totals.count.blank   += total.count.blank;
-        totals.count.comment += total.count.comment;
-        totals.count.lines   += total.count.lines;
+        totals.files += total.files;
+        totals.count.merge(&total.count);
     }
-
-    println!("{}", linesep);
-    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-             "Total",
-             totals.files,
-             totals.count.lines,
-             totals.count.blank,
-             totals.count.comment,
-             totals.count.code);
-    println!("{}", linesep);
+    totals
 }
@@ -0,0 +1,55 @@
+// Vim and Emacs modelines, which name a file's language from inside it. Like Vim, only
+// the first and last few lines are looked at.
+
+use std::sync::OnceLock;
+
+use memchr::memchr_iter;
+use regex::Regex;
+
+/// How many lines at each end of a file can have a modeline.
+const MODELINE_LINES: usize = 5;
+
+/// The language a modeline names, as the editor names it ("python", "c++", "sh"...):
+///
+///   # vim: set ft=python:
+///   /* -*- mode: c++; indent-tabs-mode: nil -*- */
+pub fn modeline_language(contents: &[u8]) -> Option<&str> {
+    let head_len = memchr_iter(b'\n', contents)
+        .nth(MODELINE_LINES - 1)
+        .map_or(contents.len(), |i| i + 1);
+    let (head, rest) = contents.split_at(head_len);
+    // modelines are ASCII, so a file that isn't UTF-8 can still have one
+    head.split(|&b| b == b'\n')
+        .chain(rest.rsplit(|&b| b == b'\n').take(MODELINE_LINES))
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .find_map(|line| vim(line).or_else(|| emacs(line)))
+}
+
+/// `vim: set ft=python:`, `vi: filetype=ruby` or `ex: syntax=sh`.
+fn vim(line: &str) -> Option<&str> {
+    static VIM: OnceLock<Regex> = OnceLock::new();
+    if !line.contains("vi") && !line.contains("ex:") {
+        return None;
+    }
+    VIM.get_or_init(|| {
+        Regex::new(r"(?:^|\s)(?:vi|vim|Vim|ex)(?:[<=>]?\d+)?:(?:.*?[:\s])?(?:ft|filetype|syntax)=([\w+#-]+)")
+            .expect("vim modeline regex")
+    })
+    .captures(line)
+    .and_then(|caps| caps.get(1))
+    .map(|m| m.as_str())
+}
+
+/// `-*- mode: ruby -*-`, or just `-*- ruby -*-`.
+fn emacs(line: &str) -> Option<&str> {
+    let start = line.find("-*-")? + 3;
+    let len = line[start..].find("-*-")?;
+    let vars = &line[start..start + len];
+    if !vars.contains(':') {
+        return Some(vars.trim()).filter(|mode| !mode.is_empty());
+    }
+    vars.split(';')
+        .filter_map(|var| var.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("mode"))
+        .map(|(_, mode)| mode.trim())
+}
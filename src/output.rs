@@ -0,0 +1,646 @@
+// Machine-readable report formats. The table printer lives in main.rs, everything
+// here takes the same aggregated data so the numbers always match.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde_json;
+
+use loc::*;
+use FileCount;
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    languages: Vec<JsonLang<'a>>,
+    total: LangTotal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<JsonFile<'a>>>,
+}
+
+#[derive(Serialize)]
+struct JsonLang<'a> {
+    language: &'a str,
+    #[serde(flatten)]
+    total: &'a LangTotal,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    embedded: Vec<JsonEmbedded>,
+}
+
+#[derive(Serialize)]
+struct JsonEmbedded {
+    language: String,
+    #[serde(flatten)]
+    total: LangTotal,
+}
+
+#[derive(Serialize)]
+struct JsonFile<'a> {
+    path: &'a str,
+    language: &'a str,
+    #[serde(flatten)]
+    count: &'a Count,
+    line_endings: LineEndings,
+    max_line_length: u32,
+    mean_line_length: f64,
+    long_lines: u32,
+    minified: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    embedded: Vec<JsonFileEmbedded<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFileEmbedded<'a> {
+    language: &'a str,
+    #[serde(flatten)]
+    count: &'a Count,
+}
+
+/// Schema (see the README):
+///
+/// { "languages": [{"language", "files", "code", "comment", "doc", "mixed", "blank",
+///                  "lines"}, ...],
+///   "total":     {"files", "code", "comment", "doc", "mixed", "blank", "lines"},
+///   "files":     [{"path", "language", "code", "comment", "doc", "mixed", "blank", "lines",
+///                  "line_endings", "max_line_length", "mean_line_length", "long_lines",
+///                  "minified"}, ...] }
+///
+/// `files` is only present with --files. `line_endings` is "lf", "crlf", "cr", "mixed" or
+/// "none". Line lengths are in characters, `long_lines` are longer than --long-line (120).
+/// Languages and files are in --sort order. With --embedded nest, languages with embedded
+/// languages also have `"embedded": [{"language", "files", "code", ...}, ...]`. With either
+/// --embedded mode, files with embedded languages have
+/// `"embedded": [{"language", "code", ...}, ...]`, which nest includes in the file's counts
+/// and split doesn't.
+pub fn print_json(totals_by_lang: &[(&&Lang, &LangTotal)],
+                  by_lang: &HashMap<Lang, Vec<FileCount>>,
+                  by_file: bool) {
+    let languages = totals_by_lang.iter()
+        .map(|&(lang, total)| JsonLang {
+            language: lang.to_s(),
+            total,
+            embedded: ::embedded_totals(&by_lang[*lang]).into_iter()
+                .map(|(lang, total)| JsonEmbedded { language: String::from(lang.to_s()), total })
+                .collect(),
+        })
+        .collect();
+
+    let files = if by_file {
+        Some(totals_by_lang.iter()
+             .flat_map(|&(lang, _)| by_lang[*lang].iter())
+             .map(|fc| JsonFile {
+                 path: &fc.path,
+                 language: fc.lang.to_s(),
+                 count: &fc.count,
+                 line_endings: fc.line_endings,
+                 max_line_length: fc.lengths.max,
+                 mean_line_length: mean_line_length(&fc.lengths),
+                 long_lines: fc.lengths.long,
+                 minified: fc.minified,
+                 embedded: fc.embedded.iter().chain(&fc.split)
+                     .map(|(lang, count)| JsonFileEmbedded { language: lang.to_s(), count })
+                     .collect(),
+             })
+             .collect())
+    } else {
+        None
+    };
+
+    let report = JsonReport {
+        languages,
+        total: ::grand_total(totals_by_lang),
+        files,
+    };
+
+    println!("{}", serde_json::to_string_pretty(&report).expect("serializing report"));
+}
+
+/// The mean rounded to one decimal, like the table shows it.
+fn mean_line_length(lengths: &LineLengths) -> f64 {
+    (lengths.mean() * 10.0).round() / 10.0
+}
+
+/// One row per language, or one row per file with --files. Paths are written in full
+/// and quoted when they contain the separator, a quote or a newline.
+pub fn print_delimited(totals_by_lang: &[(&&Lang, &LangTotal)],
+                       by_lang: &HashMap<Lang, Vec<FileCount>>,
+                       by_file: bool,
+                       columns: Columns,
+                       file_columns: FileColumns,
+                       sep: char,
+                       header: bool) {
+    let row = |fields: &[String]| {
+        let quoted = fields.iter().map(|f| quote_field(f, sep)).collect::<Vec<_>>();
+        println!("{}", quoted.join(&sep.to_string()));
+    };
+
+    if by_file {
+        if header {
+            let mut headers = columns.headers();
+            headers.extend(file_columns.headers());
+            row(&with_count_fields(&["Path", "Language"], headers));
+        }
+        for &(lang, _) in totals_by_lang {
+            for fc in &by_lang[*lang] {
+                let mut fields = columns.fields(&fc.count);
+                fields.extend(file_columns.fields(fc));
+                row(&with_count_fields(&[&fc.path, fc.lang.to_s()], fields));
+            }
+        }
+    } else {
+        if header {
+            row(&with_count_fields(&["Language", "Files"], columns.headers()));
+        }
+        for &(lang, total) in totals_by_lang {
+            row(&with_count_fields(&[lang.to_s(), &total.files.to_string()], columns.fields(&total.count)));
+        }
+    }
+}
+
+/// The optional count columns of the tabular formats, added with --doc and --mixed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Columns {
+    pub doc: bool,
+    pub mixed: bool,
+}
+
+impl Columns {
+    /// Lines, Blank, Comment, Doc, Mixed and Code, leaving out the optional columns that
+    /// weren't asked for.
+    pub fn headers(&self) -> Vec<String> {
+        self.pick(["Lines", "Blank", "Comment", "Doc", "Mixed", "Code"])
+            .into_iter().map(String::from).collect()
+    }
+
+    pub fn fields(&self, count: &Count) -> Vec<String> {
+        self.pick([count.lines, count.blank, count.comment, count.doc, count.mixed, count.code])
+            .into_iter().map(|n| n.to_string()).collect()
+    }
+
+    fn pick<T>(&self, [lines, blank, comment, doc, mixed, code]: [T; 6]) -> Vec<T> {
+        let mut picked = vec![lines, blank, comment];
+        if self.doc {
+            picked.push(doc);
+        }
+        if self.mixed {
+            picked.push(mixed);
+        }
+        picked.push(code);
+        picked
+    }
+}
+
+/// The per-file columns of --files, added with --line-endings and --line-lengths.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileColumns {
+    pub line_endings: bool,
+    pub line_lengths: bool,
+}
+
+impl FileColumns {
+    pub fn headers(&self) -> Vec<String> {
+        let mut headers = vec![];
+        if self.line_endings {
+            headers.push("Endings");
+        }
+        if self.line_lengths {
+            headers.extend(&["Max Line", "Mean Line", "Long Lines"]);
+        }
+        headers.into_iter().map(String::from).collect()
+    }
+
+    pub fn fields(&self, fc: &FileCount) -> Vec<String> {
+        let mut fields = vec![];
+        if self.line_endings {
+            fields.push(fc.line_endings.to_string());
+        }
+        if self.line_lengths {
+            fields.push(fc.lengths.max.to_string());
+            fields.push(format!("{:.1}", fc.lengths.mean()));
+            fields.push(fc.lengths.long.to_string());
+        }
+        fields
+    }
+}
+
+pub fn with_count_fields(first: &[&str], counts: Vec<String>) -> Vec<String> {
+    first.iter().map(|s| String::from(*s)).chain(counts).collect()
+}
+
+fn quote_field(field: &str, sep: char) -> String {
+    if field.contains(&[sep, '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// The header block cloc puts at the top of its --json, --yaml and --xml reports.
+struct ClocHeader {
+    elapsed_seconds: f64,
+    n_files: u32,
+    n_lines: u32,
+}
+
+impl ClocHeader {
+    fn new(total: &LangTotal, elapsed: Duration) -> ClocHeader {
+        ClocHeader {
+            elapsed_seconds: elapsed.as_secs_f64(),
+            n_files: total.files,
+            n_lines: total.count.lines,
+        }
+    }
+
+    fn per_second(&self, n: u32) -> f64 {
+        if self.elapsed_seconds > 0.0 {
+            f64::from(n) / self.elapsed_seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+const CLOC_URL: &str = "github.com/cgag/loc";
+
+/// Reproduces the shape of `cloc --json` (or `cloc --json --by-file` with --files):
+/// a "header" object, one object per language (or file), and a "SUM" object.
+pub fn print_cloc_json(totals_by_lang: &[(&&Lang, &LangTotal)],
+                       by_lang: &HashMap<Lang, Vec<FileCount>>,
+                       by_file: bool,
+                       elapsed: Duration) {
+    let total = ::grand_total(totals_by_lang);
+    let header = ClocHeader::new(&total, elapsed);
+    let js = |s: &str| serde_json::to_string(s).expect("serializing string");
+
+    println!("{{\"header\" : {{");
+    println!("  \"cloc_url\"           : {},", js(CLOC_URL));
+    println!("  \"cloc_version\"       : {},", js(env!("CARGO_PKG_VERSION")));
+    println!("  \"elapsed_seconds\"    : {},", header.elapsed_seconds);
+    println!("  \"n_files\"            : {},", header.n_files);
+    println!("  \"n_lines\"            : {},", header.n_lines);
+    println!("  \"files_per_second\"   : {},", header.per_second(header.n_files));
+    println!("  \"lines_per_second\"   : {}}},", header.per_second(header.n_lines));
+
+    if by_file {
+        for &(lang, _) in totals_by_lang {
+            for fc in &by_lang[*lang] {
+                println!("{} :{{", js(&fc.path));
+                println!("  \"blank\": {},", fc.count.blank);
+                println!("  \"comment\": {},", fc.count.comment);
+                println!("  \"code\": {},", fc.count.code);
+                println!("  \"language\": {}}},", js(fc.lang.to_s()));
+            }
+        }
+    } else {
+        for &(lang, lang_total) in totals_by_lang {
+            println!("{} :{{", js(lang.to_s()));
+            println!("  \"nFiles\": {},", lang_total.files);
+            println!("  \"blank\": {},", lang_total.count.blank);
+            println!("  \"comment\": {},", lang_total.count.comment);
+            println!("  \"code\": {}}},", lang_total.count.code);
+        }
+    }
+
+    println!("\"SUM\": {{");
+    println!("  \"blank\": {},", total.count.blank);
+    println!("  \"comment\": {},", total.count.comment);
+    println!("  \"code\": {},", total.count.code);
+    println!("  \"nFiles\": {}}} }}", total.files);
+}
+
+/// Reproduces the shape of `cloc --yaml`, see print_cloc_json.
+pub fn print_cloc_yaml(totals_by_lang: &[(&&Lang, &LangTotal)],
+                       by_lang: &HashMap<Lang, Vec<FileCount>>,
+                       by_file: bool,
+                       elapsed: Duration) {
+    let total = ::grand_total(totals_by_lang);
+    let header = ClocHeader::new(&total, elapsed);
+
+    println!("---");
+    println!("# {}", CLOC_URL);
+    println!("header :");
+    println!("  cloc_url           : {}", CLOC_URL);
+    println!("  cloc_version       : {}", env!("CARGO_PKG_VERSION"));
+    println!("  elapsed_seconds    : {}", header.elapsed_seconds);
+    println!("  n_files            : {}", header.n_files);
+    println!("  n_lines            : {}", header.n_lines);
+    println!("  files_per_second   : {}", header.per_second(header.n_files));
+    println!("  lines_per_second   : {}", header.per_second(header.n_lines));
+
+    if by_file {
+        for &(lang, _) in totals_by_lang {
+            for fc in &by_lang[*lang] {
+                println!("{} :", yaml_key(&fc.path));
+                println!("  blank: {}", fc.count.blank);
+                println!("  comment: {}", fc.count.comment);
+                println!("  code: {}", fc.count.code);
+                println!("  language: {}", yaml_key(fc.lang.to_s()));
+            }
+        }
+    } else {
+        for &(lang, lang_total) in totals_by_lang {
+            println!("{} :", yaml_key(lang.to_s()));
+            println!("  nFiles: {}", lang_total.files);
+            println!("  blank: {}", lang_total.count.blank);
+            println!("  comment: {}", lang_total.count.comment);
+            println!("  code: {}", lang_total.count.code);
+        }
+    }
+
+    println!("SUM:");
+    println!("  blank: {}", total.count.blank);
+    println!("  comment: {}", total.count.comment);
+    println!("  code: {}", total.count.code);
+    println!("  nFiles: {}", total.files);
+}
+
+/// Reproduces the shape of `cloc --xml`, see print_cloc_json.
+pub fn print_cloc_xml(totals_by_lang: &[(&&Lang, &LangTotal)],
+                      by_lang: &HashMap<Lang, Vec<FileCount>>,
+                      by_file: bool,
+                      elapsed: Duration) {
+    let total = ::grand_total(totals_by_lang);
+    let header = ClocHeader::new(&total, elapsed);
+
+    println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><results>");
+    println!("<header>");
+    println!("  <cloc_url>{}</cloc_url>", CLOC_URL);
+    println!("  <cloc_version>{}</cloc_version>", env!("CARGO_PKG_VERSION"));
+    println!("  <elapsed_seconds>{}</elapsed_seconds>", header.elapsed_seconds);
+    println!("  <n_files>{}</n_files>", header.n_files);
+    println!("  <n_lines>{}</n_lines>", header.n_lines);
+    println!("  <files_per_second>{}</files_per_second>", header.per_second(header.n_files));
+    println!("  <lines_per_second>{}</lines_per_second>", header.per_second(header.n_lines));
+    println!("</header>");
+
+    if by_file {
+        println!("<files>");
+        for &(lang, _) in totals_by_lang {
+            for fc in &by_lang[*lang] {
+                println!("  <file name=\"{}\" blank=\"{}\" comment=\"{}\" code=\"{}\"  language=\"{}\"/>",
+                         xml_escape(&fc.path),
+                         fc.count.blank,
+                         fc.count.comment,
+                         fc.count.code,
+                         xml_escape(fc.lang.to_s()));
+            }
+        }
+        println!("  <total blank=\"{}\" comment=\"{}\" code=\"{}\" />",
+                 total.count.blank,
+                 total.count.comment,
+                 total.count.code);
+        println!("</files>");
+    } else {
+        println!("<languages>");
+        for &(lang, lang_total) in totals_by_lang {
+            println!("  <language name=\"{}\" files_count=\"{}\" blank=\"{}\" comment=\"{}\" code=\"{}\" />",
+                     xml_escape(lang.to_s()),
+                     lang_total.files,
+                     lang_total.count.blank,
+                     lang_total.count.comment,
+                     lang_total.count.code);
+        }
+        println!("  <total sum_files=\"{}\" blank=\"{}\" comment=\"{}\" code=\"{}\" />",
+                 total.files,
+                 total.count.blank,
+                 total.count.comment,
+                 total.count.code);
+        println!("</languages>");
+    }
+    println!("</results>");
+}
+
+// Plain keys (most language names) are written bare like cloc does, anything that
+// could confuse a YAML parser (paths with ": ", "C#", ...) gets double quoted.
+fn yaml_key(s: &str) -> String {
+    let plain = !s.is_empty()
+        && !s.starts_with(&['-', ' '][..])
+        && !s.ends_with(' ')
+        && s.chars().all(|c| c.is_alphanumeric() || " _./+-()".contains(c));
+    if plain {
+        String::from(s)
+    } else {
+        serde_json::to_string(s).expect("serializing string")
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&'  => escaped.push_str("&amp;"),
+            '<'  => escaped.push_str("&lt;"),
+            '>'  => escaped.push_str("&gt;"),
+            '"'  => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c    => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// GitHub flavored markdown: the language table, followed by a table of files with --files.
+pub fn print_markdown(totals_by_lang: &[(&&Lang, &LangTotal)],
+                      by_lang: &HashMap<Lang, Vec<FileCount>>,
+                      by_file: bool,
+                      columns: Columns) {
+    let total = ::grand_total(totals_by_lang);
+    let row = |fields: &[String]| println!("| {} |", fields.join(" | "));
+    let align = |fields: &[String], left: usize| {
+        let cells = fields.iter().enumerate().map(|(i, f)| {
+            let dashes = "-".repeat(f.len() + 1);
+            if i < left { format!(":{}", dashes) } else { format!("{}:", dashes) }
+        });
+        println!("|{}|", cells.collect::<Vec<_>>().join("|"));
+    };
+
+    let header = with_count_fields(&["Language", "Files"], columns.headers());
+    row(&header);
+    align(&header, 1);
+    for &(lang, lang_total) in totals_by_lang {
+        row(&with_count_fields(&[&md_escape(lang.to_s()), &lang_total.files.to_string()],
+                               columns.fields(&lang_total.count)));
+    }
+    let bold = |n: String| format!("**{}**", n);
+    row(&with_count_fields(&["**Total**", &bold(total.files.to_string())],
+                           columns.fields(&total.count).into_iter().map(bold).collect()));
+
+    if by_file {
+        println!();
+        let header = with_count_fields(&["File", "Language"], columns.headers());
+        row(&header);
+        align(&header, 2);
+        for &(lang, _) in totals_by_lang {
+            for fc in &by_lang[*lang] {
+                row(&with_count_fields(&[&md_escape(&fc.path), &md_escape(fc.lang.to_s())],
+                                       columns.fields(&fc.count)));
+            }
+        }
+    }
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>loc report</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; }
+th { cursor: pointer; user-select: none; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+tfoot td { font-weight: bold; }
+.bar { background: #eee; width: 12em; height: 0.8em; }
+.bar div { background: #4878a8; height: 100%; }
+</style>
+</head>
+<body>
+"#;
+
+// Clicking a header sorts that table's body by the column, clicking again reverses it.
+// Cells carry a data-sort value so numbers sort numerically.
+const HTML_TAIL: &str = r#"<script>
+document.querySelectorAll("table.sortable").forEach(function (table) {
+  table.querySelectorAll("th").forEach(function (th, col) {
+    th.addEventListener("click", function () {
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      var asc = th.dataset.order !== "asc";
+      th.dataset.order = asc ? "asc" : "desc";
+      rows.sort(function (a, b) {
+        var x = a.cells[col].dataset.sort, y = b.cells[col].dataset.sort;
+        var nx = Number(x), ny = Number(y);
+        var cmp = isNaN(nx) || isNaN(ny) ? x.localeCompare(y) : nx - ny;
+        return asc ? cmp : -cmp;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+</script>
+</body>
+</html>
+"#;
+
+/// A standalone page with sortable tables and a bar showing each language's share of the code.
+pub fn print_html(totals_by_lang: &[(&&Lang, &LangTotal)],
+                  by_lang: &HashMap<Lang, Vec<FileCount>>,
+                  by_file: bool,
+                  columns: Columns) {
+    let total = ::grand_total(totals_by_lang);
+    let headers = |first: &[&str]| {
+        with_count_fields(first, columns.headers()).iter()
+            .map(|h| format!("<th>{}</th>", h))
+            .collect::<String>()
+    };
+    let nums = |count: &Count| {
+        columns.fields(count).iter().map(|n| html_num(n)).collect::<String>()
+    };
+
+    print!("{}", HTML_HEAD);
+    println!("<h1>Lines of code</h1>");
+    println!("<table class=\"sortable\">");
+    println!("<thead><tr>{}<th>% of code</th></tr></thead>", headers(&["Language", "Files"]));
+    println!("<tbody>");
+    for &(lang, lang_total) in totals_by_lang {
+        let percent = if total.count.code > 0 {
+            100.0 * f64::from(lang_total.count.code) / f64::from(total.count.code)
+        } else {
+            0.0
+        };
+        println!("<tr>{}{}{}<td data-sort=\"{:.2}\"><div class=\"bar\" title=\"{:.1}%\">\
+                  <div style=\"width: {:.2}%\"></div></div></td></tr>",
+                 html_cell(lang.to_s()),
+                 html_num(&lang_total.files.to_string()),
+                 nums(&lang_total.count),
+                 percent,
+                 percent,
+                 percent);
+    }
+    println!("</tbody>");
+    println!("<tfoot><tr><td>Total</td>{}{}<td></td></tr></tfoot>",
+             html_num(&total.files.to_string()),
+             nums(&total.count));
+    println!("</table>");
+
+    if by_file {
+        println!("<h2>Files</h2>");
+        println!("<table class=\"sortable\">");
+        println!("<thead><tr>{}</tr></thead>", headers(&["File", "Language"]));
+        println!("<tbody>");
+        for &(lang, _) in totals_by_lang {
+            for fc in &by_lang[*lang] {
+                println!("<tr>{}{}{}</tr>",
+                         html_cell(&fc.path),
+                         html_cell(fc.lang.to_s()),
+                         nums(&fc.count));
+            }
+        }
+        println!("</tbody>");
+        println!("</table>");
+    }
+    print!("{}", HTML_TAIL);
+}
+
+fn html_cell(s: &str) -> String {
+    let escaped = xml_escape(s);
+    format!("<td data-sort=\"{}\">{}</td>", escaped, escaped)
+}
+
+fn html_num(n: &str) -> String {
+    format!("<td class=\"num\" data-sort=\"{}\">{}</td>", n, n)
+}
+
+fn md_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+fn sorted_languages(languages: &LanguageRegistry) -> Vec<&Language> {
+    let mut sorted = languages.languages().collect::<Vec<_>>();
+    sorted.sort_by_key(|l| l.name.to_lowercase());
+    sorted
+}
+
+/// For --list-languages. Each language gets two lines: how its files are recognized
+/// (.ext, filename, #!interpreter), then its comment markers.
+pub fn print_languages_table(languages: &LanguageRegistry) {
+    let linesep = ::str_repeat("-", 80);
+    println!("{}", linesep);
+    println!(" {0: <20} Extensions, filenames and shebangs", "Language");
+    println!(" {0: <20} Comments", "");
+    println!("{}", linesep);
+
+    for language in sorted_languages(languages) {
+        let recognized = language.extensions.iter().map(|ext| format!(".{}", ext))
+            .chain(language.filenames.iter().cloned())
+            .chain(language.filename_patterns.iter().cloned())
+            .chain(language.shebangs.iter().map(|shebang| format!("#!{}", shebang)))
+            .collect::<Vec<_>>();
+
+        let mut comments = language.line_comments.clone();
+        comments.extend(language.block_comments.iter().map(|(start, end)| format!("{} {}", start, end)));
+        let mut comments = comments.join("   ");
+        if let Some(literate) = language.literate {
+            comments = String::from(match literate {
+                Literate::Bird     => "literate: > bird tracks, \\begin{code} blocks",
+                Literate::Latex    => "literate: \\begin{code} blocks",
+                Literate::Indented => "literate: indented code blocks",
+            });
+        } else if comments.is_empty() {
+            comments.push_str("(none)");
+        } else if language.nested && !language.block_comments.is_empty() {
+            comments.push_str("   (nested)");
+        }
+        if !language.doc_comments.is_empty() {
+            comments.push_str(&format!("   docs: {}", language.doc_comments.join(" ")));
+        }
+
+        println!(" {0: <20} {1}", language.name, recognized.join(" "));
+        println!(" {0: <20} {1}", "", comments);
+    }
+    println!("{}", linesep);
+}
+
+pub fn print_languages_json(languages: &LanguageRegistry) {
+    println!("{}", serde_json::to_string_pretty(&sorted_languages(languages)).expect("serializing languages"));
+}
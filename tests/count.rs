@@ -1,3 +1,4 @@
+
 extern crate loc;
 
 use loc::*;
@@ -32,160 +33,304 @@ macro_rules! test_count {
     )
 }
 
+#[allow(clippy::redundant_static_lifetimes)]
 const PLASMA: &'static str = "tests/data/plasma.c";
 const PLASMA_EXPECTED: Count = Count {
     code: 32032,
     blank: 8848,
     comment: 3792,
+    doc: 0,
+    mixed: 240,
     lines: 44672,
 };
 
 test_count![PLASMA, PLASMA_EXPECTED, t_plasma_count, t_plasma_code, t_plasma_comment, t_plasma_blank, t_plasma_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const FE: &'static str = "tests/data/fe25519.c";
 const FE_EXPECTED: Count = Count {
     code: 278,
     blank: 51,
     comment: 8,
+    doc: 0,
+    mixed: 84,
     lines: 278 + 51 + 8,
 };
 
 test_count![FE, FE_EXPECTED, test_fe_count, test_fe_code, test_fe_comment, test_fe_blank, test_fe_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const EBC: &'static str = "tests/data/ebcdic.c";
 const EBC_EXPECTED: Count = Count {
     code: 165,
     blank: 18,
     comment: 101,
+    doc: 0,
+    mixed: 131,
     lines: 165 + 18 + 101,
 };
 
 test_count![EBC, EBC_EXPECTED, ebc_count, ebc_code, ebc_comment, evc_blank, ebc_lines];
 
+// The same file in different encodings, with a byte order mark unless it says otherwise.
+const ENCODED_EXPECTED: Count = Count {
+    code: 4,
+    blank: 1,
+    comment: 3,
+    doc: 0,
+    mixed: 1,
+    lines: 8,
+};
+const UTF8_BOM: &str = "tests/data/utf8_bom.c";
+test_count![UTF8_BOM, ENCODED_EXPECTED, utf8_bom_count, utf8_bom_code, utf8_bom_comment, utf8_bom_blank, utf8_bom_lines];
+const UTF16LE: &str = "tests/data/utf16le.c";
+test_count![UTF16LE, ENCODED_EXPECTED, utf16le_count, utf16le_code, utf16le_comment, utf16le_blank, utf16le_lines];
+const UTF16BE: &str = "tests/data/utf16be.c";
+test_count![UTF16BE, ENCODED_EXPECTED, utf16be_count, utf16be_code, utf16be_comment, utf16be_blank, utf16be_lines];
+const UTF16LE_NO_BOM: &str = "tests/data/utf16le_no_bom.c";
+test_count![UTF16LE_NO_BOM, ENCODED_EXPECTED, utf16le_no_bom_count, utf16le_no_bom_code, utf16le_no_bom_comment,
+            utf16le_no_bom_blank, utf16le_no_bom_lines];
+const LATIN1: &str = "tests/data/latin1.c";
+test_count![LATIN1, ENCODED_EXPECTED, latin1_count, latin1_code, latin1_comment, latin1_blank, latin1_lines];
+
+#[test]
+fn decode_with_encoding() {
+    let latin1 = std::fs::read(LATIN1).unwrap();
+    let text = decode_source(&latin1, Encoding::Latin1).unwrap();
+    assert!(text.starts_with("/* Überprüfung"));
+    assert_eq!(text, decode_source(&latin1, Encoding::Auto).unwrap());
+
+    // the byte order mark isn't part of the first line
+    let bom = std::fs::read(UTF8_BOM).unwrap();
+    assert_eq!(text, decode_source(&bom, Encoding::Utf8).unwrap());
+    assert_eq!(text, decode_source(&std::fs::read(UTF16BE).unwrap(), Encoding::Utf16Be).unwrap());
+    assert_eq!(text, decode_source(&std::fs::read(UTF16LE_NO_BOM).unwrap(), Encoding::Utf16Le).unwrap());
+
+    // UTF-16 has NULs, but isn't binary
+    assert!(!is_binary(&std::fs::read(UTF16LE).unwrap()));
+    assert!(!is_binary(&std::fs::read(UTF16LE_NO_BOM).unwrap()));
+    match decode_source(&std::fs::read("tests/data/binary.c").unwrap(), Encoding::Latin1) {
+        Err(CountError::Binary) => (),
+        other => panic!("expected a binary file error, got {:?}", other),
+    }
+
+    assert_eq!(Ok(Encoding::Utf16Le), "UTF-16LE".parse());
+    assert_eq!(Ok(Encoding::Latin1), "iso-8859-1".parse());
+    assert!("ebcdic".parse::<Encoding>().is_err());
+}
+
+#[test]
+fn detect_utf16() {
+    let script: Vec<u8> = "#!/usr/bin/env python\nprint(1)\n".encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+    assert_eq!(Lang::Python, lang_from_buffer("script", &script));
+}
+
+#[allow(clippy::redundant_static_lifetimes)]
 const DUMB: &'static str = "tests/data/dumb.c";
 const DUMB_EXPECTED: Count = Count {
     code: 2,
     blank: 0,
     comment: 3,
+    doc: 0,
+    mixed: 1,
     lines: 5,
 };
 test_count![DUMB, DUMB_EXPECTED, dumb_count, dumb_code, dumb_comment, dumb_blank, dumb_lines];
 
+// dumb.c with a blank line, saved with \r\n, old Mac \r and a mix of line endings
+const ENDINGS_EXPECTED: Count = Count {
+    code: 2,
+    blank: 1,
+    comment: 3,
+    doc: 0,
+    mixed: 1,
+    lines: 6,
+};
+const CRLF: &str = "tests/data/crlf.c";
+test_count![CRLF, ENDINGS_EXPECTED, crlf_count, crlf_code, crlf_comment, crlf_blank, crlf_lines];
+const CR: &str = "tests/data/cr.c";
+test_count![CR, ENDINGS_EXPECTED, cr_count, cr_code, cr_comment, cr_blank, cr_lines];
+const MIXED_ENDINGS: &str = "tests/data/mixed_endings.c";
+test_count![MIXED_ENDINGS, ENDINGS_EXPECTED, mixed_endings_count, mixed_endings_code, mixed_endings_comment,
+            mixed_endings_blank, mixed_endings_lines];
+
+#[test]
+fn line_ending_styles() {
+    assert_eq!(LineEndings::CrLf, line_endings(&std::fs::read(CRLF).unwrap()));
+    assert_eq!(LineEndings::Cr, line_endings(&std::fs::read(CR).unwrap()));
+    assert_eq!(LineEndings::Mixed, line_endings(&std::fs::read(MIXED_ENDINGS).unwrap()));
+    assert_eq!(LineEndings::Lf, line_endings(&std::fs::read(DUMB).unwrap()));
+    assert_eq!(LineEndings::None, line_endings(b"one line"));
+    assert_eq!(LineEndings::None, line_endings(b""));
+    assert_eq!(LineEndings::Mixed, line_endings(b"a\r\nb\n"));
+}
+
+#[test]
+fn carriage_returns_are_line_endings() {
+    // a line that's only a \r is blank
+    assert_eq!(Count { code: 2, comment: 0, doc: 0, mixed: 0, blank: 1, lines: 3 },
+               count_bytes(Lang::C, b"x;\n\r\ny;\n"));
+    assert_eq!(Count { code: 2, comment: 0, doc: 0, mixed: 0, blank: 1, lines: 3 },
+               count_bytes(Lang::C, b"x;\r\ry;"));
+    assert_eq!(Count { code: 1, comment: 1, doc: 1, mixed: 0, blank: 1, lines: 3 },
+               count_bytes(Lang::LiterateHaskell, b"prose\r>\r> main = pure ()\r"));
+}
+
+#[allow(clippy::redundant_static_lifetimes)]
 const IPL: &'static str = "tests/data/ipl_funcs.c";
 const IPL_EXPECTED: Count = Count {
     code: 25,
     blank: 6,
     comment: 43,
+    doc: 0,
+    mixed: 0,
     lines: 25 + 6 + 43,
 };
 test_count![IPL, IPL_EXPECTED, ipl_count, ipl_code, ipl_comment, ipl_blank, ipl_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const LUA: &'static str = "tests/data/lua.lua";
 const LUA_EXPECTED: Count = Count {
     code: 7,
     blank: 1,
     comment: 8,
+    doc: 0,
+    mixed: 0,
     lines: 7 + 8 + 1,
 };
 test_count![LUA, LUA_EXPECTED, lua_count, lua_code, lua_comment, lua_blank, lua_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const RUBY: &'static str = "tests/data/test.rb";
 const RUBY_EXPECTED: Count = Count {
     code: 2,
     blank: 0,
     comment: 2,
+    doc: 0,
+    mixed: 0,
     lines: 2+2,
 };
 test_count![RUBY, RUBY_EXPECTED, ruby_count, ruby_code, ruby_comment, ruby_blank, ruby_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const OCAML: &'static str = "tests/data/ocaml.ml";
 const OCAML_EXPECTED: Count = Count {
     code: 3,
     blank: 4,
     comment: 6,
+    doc: 0,
+    mixed: 0,
     lines: 3+4+6,
 };
 test_count![OCAML, OCAML_EXPECTED, ocaml_count, ocaml_code, ocaml_comment, ocaml_blank, ocaml_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const REASON: &'static str = "tests/data/reason.re";
 const REASON_EXPECTED: Count = Count {
     code: 3,
     blank: 4,
     comment: 6,
+    doc: 0,
+    mixed: 0,
     lines: 3+4+6,
 };
 test_count![REASON, REASON_EXPECTED, reason_count, reason_code, reason_comment, reason_blank, reason_lines];
 
 
 // single only
+#[allow(clippy::redundant_static_lifetimes)]
 const ADA: &'static str = "tests/data/ada.ada";
 const ADA_EXPECTED: Count = Count {
     code: 4,
     blank: 0,
     comment: 3,
+    doc: 0,
+    mixed: 0,
     lines: 4+3,
 };
 test_count![ADA, ADA_EXPECTED, ada_count, ada_code, ada_comment, ada_blank, ada_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const GHERKIN: &'static str = "tests/data/gherkin.feature";
 const GHERKIN_EXPECTED: Count = Count {
     code: 8,
     blank: 2,
     comment: 2,
+    doc: 0,
+    mixed: 0,
     lines: 8+2+2,
 };
 test_count![GHERKIN, GHERKIN_EXPECTED, gherkin_count, gherkin_code, gherkin_comment, gherkin_blank, gherkin_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const GROOVY: &'static str = "tests/data/test.groovy";
 const GROOVY_EXPECTED: Count = Count {
     code: 6,
     blank: 1,
     comment: 10,
+    doc: 10,
+    mixed: 0,
     lines: 6+1+10,
 };
 test_count![GROOVY, GROOVY_EXPECTED, groovy_count, groovy_code, groovy_comment, groovy_blank, groovy_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const TERRAFORM: &'static str = "tests/data/test.tf";
 const TERRAFORM_EXPECTED: Count = Count {
     code: 65,
     blank: 13,
     comment: 11,
+    doc: 0,
+    mixed: 0,
     lines: 65+13+11,
 };
 test_count![TERRAFORM, TERRAFORM_EXPECTED, terraform_count, terraform_code, terraform_comment, terraform_blank, terraform_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const ZIG: &'static str = "tests/data/zig.zig";
 const ZIG_EXPECTED: Count = Count {
     code: 5,
     blank: 2,
     comment: 2,
+    doc: 1,
+    mixed: 0,
     lines: 5+2+2,
 };
 test_count![ZIG, ZIG_EXPECTED, zig_count, zig_code, zig_comment, zig_blank, zig_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const NIX: &'static str = "tests/data/test.nix";
 const NIX_EXPECTED: Count = Count {
     code: 3,
     blank: 2,
     comment: 3,
+    doc: 0,
+    mixed: 1,
     lines: 3+2+3,
 };
 test_count![NIX, NIX_EXPECTED, nix_count, nix_code, nix_comment, nix_blank, nix_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const POWERSHELL: &'static str = "tests/data/test.ps1";
 const POWERSHELL_EXPECTED: Count = Count {
     code: 2,
     blank: 1,
     comment: 6,
+    doc: 0,
+    mixed: 1,
     lines: 2+1+6,
 };
 test_count![POWERSHELL, POWERSHELL_EXPECTED, powershell_count, powershell_code, powershell_comment, powershell_blank, powershell_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const HANDLEBARS: &'static str = "tests/data/test.handlebars";
+#[allow(clippy::identity_op)]
 const HANDLEBARS_EXPECTED: Count = Count {
     code: 2,
     blank: 0,
     comment: 2,
+    doc: 0,
+    mixed: 0,
     lines: 2+0+2,
 };
 test_count![HANDLEBARS,
@@ -196,11 +341,14 @@ test_count![HANDLEBARS,
             handlebars_blank,
             handlebars_lines];
 
+#[allow(clippy::redundant_static_lifetimes)]
 const NESTED_HASKELL: &'static str = "tests/data/nested-comments.hs";
 const NESTED_HASKELL_EXPECTED: Count = Count {
     code: 2,
     blank: 4,
     comment: 8,
+    doc: 0,
+    mixed: 0,
     lines: 2+4+8,
 };
 test_count![NESTED_HASKELL,
@@ -211,11 +359,48 @@ test_count![NESTED_HASKELL,
             nested_haskell_blank,
             nested_haskell_lines];
 
+const NON_NESTED_C: &str = "tests/data/non-nested-comments.c";
+const NON_NESTED_C_EXPECTED: Count = Count {
+    code: 3,
+    blank: 1,
+    comment: 3,
+    doc: 0,
+    mixed: 2,
+    lines: 3+1+3,
+};
+test_count![NON_NESTED_C,
+            NON_NESTED_C_EXPECTED,
+            non_nested_c_count,
+            non_nested_c_code,
+            non_nested_c_comment,
+            non_nested_c_blank,
+            non_nested_c_lines];
+
+const NESTED_RUST: &str = "tests/data/nested-comments.rs";
+const NESTED_RUST_EXPECTED: Count = Count {
+    code: 2,
+    blank: 1,
+    comment: 4,
+    doc: 0,
+    mixed: 0,
+    lines: 2+1+4,
+};
+test_count![NESTED_RUST,
+            NESTED_RUST_EXPECTED,
+            nested_rust_count,
+            nested_rust_code,
+            nested_rust_comment,
+            nested_rust_blank,
+            nested_rust_lines];
+
+#[allow(clippy::redundant_static_lifetimes)]
 const SOLIDITY: &'static str = "tests/data/test.sol";
 const SOLIDITY_EXPECTED: Count = Count {
     code: 10,
     blank: 3,
     comment: 3,
+    doc: 0,
+    mixed: 0,
     lines: 10+3+3,
 };
 test_count![SOLIDITY,
@@ -225,3 +410,665 @@ test_count![SOLIDITY,
             solidity_comment,
             solidity_blank,
             solidity_lines];
+
+#[test]
+fn try_count_missing_file() {
+    match try_count("tests/data/does-not-exist.c") {
+        Err(CountError::Io(_)) => (),
+        other => panic!("expected an i/o error, got {:?}", other),
+    }
+}
+
+#[test]
+fn try_count_binary_file() {
+    match try_count("tests/data/binary.c") {
+        Err(CountError::Binary) => (),
+        other => panic!("expected a binary file error, got {:?}", other),
+    }
+}
+
+#[test]
+fn try_count_control_characters() {
+    // no NULs, but far too many control characters to be assembly
+    match try_count("tests/data/object.s") {
+        Err(CountError::Binary) => (),
+        other => panic!("expected a binary file error, got {:?}", other),
+    }
+}
+
+#[test]
+fn binary_sniff() {
+    assert!(is_binary(b"ELF\x02\x01\x01\x00\x00"));
+    assert!(is_binary(&std::fs::read("tests/data/object.s").unwrap()));
+    assert!(!is_binary(b""));
+    assert!(!is_binary(b"\x1b[1mbold\x1b[0m\tand a tab\r\n\x0c"));
+    assert!(!is_binary(&std::fs::read("tests/data/invalid_utf8.c").unwrap()));
+    assert!(!is_binary(&std::fs::read(PLASMA).unwrap()));
+    // only the first block is sniffed
+    let mut late_nul = vec![b'x'; SNIFF_LEN];
+    late_nul.push(0);
+    assert!(!is_binary(&late_nul));
+    assert!(try_count_bytes(Lang::C, &late_nul).is_err());
+}
+
+/// Hands out a block of NULs, then fails.
+struct BinaryThenBroken(usize);
+
+impl std::io::Read for BinaryThenBroken {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.0 >= SNIFF_LEN {
+            return Err(std::io::Error::other("read past the first block"));
+        }
+        let n = buf.len().min(SNIFF_LEN - self.0);
+        for b in &mut buf[..n] {
+            *b = 0;
+        }
+        self.0 += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn count_reader_stops_at_binary() {
+    match count_reader(Lang::C, BinaryThenBroken(0)) {
+        Err(CountError::Binary) => (),
+        other => panic!("expected a binary file error, got {:?}", other),
+    }
+}
+
+#[test]
+fn try_count_invalid_utf8() {
+    // read as Latin-1 unless it has to be UTF-8
+    assert_eq!(Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 2 },
+               try_count("tests/data/invalid_utf8.c").unwrap());
+    let bytes = std::fs::read("tests/data/invalid_utf8.c").unwrap();
+    match decode_source(&bytes, Encoding::Utf8) {
+        Err(CountError::InvalidEncoding { line: 1 }) => (),
+        other => panic!("expected an encoding error on line 1, got {:?}", other),
+    }
+}
+
+#[test]
+fn try_count_unrecognized() {
+    match try_count("tests/data/python_no_extension.unknownext") {
+        Err(CountError::UnrecognizedLanguage) => (),
+        other => panic!("expected an unrecognized language error, got {:?}", other),
+    }
+}
+
+#[test]
+fn count_defaults_on_error() {
+    assert_eq!(Count::default(), count("tests/data/binary.c"));
+}
+
+#[test]
+fn count_bytes_matches_count() {
+    let bytes = std::fs::read(PLASMA).unwrap();
+    assert_eq!(PLASMA_EXPECTED, count_bytes(Lang::C, &bytes));
+}
+
+#[test]
+fn count_reader_matches_count() {
+    let file = std::fs::File::open(LUA).unwrap();
+    assert_eq!(LUA_EXPECTED, count_reader(Lang::Lua, file).unwrap());
+}
+
+#[test]
+fn count_bytes_in_memory() {
+    let src = b"// a comment\n\nfn main() {}\n";
+    assert_eq!(Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 1, lines: 3 }, count_bytes(Lang::Rust, src));
+}
+
+#[test]
+fn lang_from_buffer_uses_contents_for_shebang() {
+    assert_eq!(Lang::Python, lang_from_buffer("unsaved", b"#!/usr/bin/env python\nprint(1)\n"));
+    assert_eq!(Lang::Rust, lang_from_buffer("src/new.rs", b""));
+}
+
+fn generated(path: &str, lang: Lang, text: &str) -> bool {
+    let (_, lengths) = try_count_lengths(lang, text.as_bytes()).unwrap();
+    is_generated(path, lang, text, &lengths)
+}
+
+#[test]
+fn generated_files() {
+    for path in &["tests/data/generated/src/api.pb.go", "tests/data/generated/src/schema.rs",
+                  "tests/data/generated/src/Form1.Designer.cs", "tests/data/generated/src/bundle.js"] {
+        let text = std::fs::read_to_string(path).unwrap();
+        assert!(generated(path, lang_from_ext(path), &text), "{}", path);
+    }
+    let path = "tests/data/generated/src/main.go";
+    assert!(!generated(path, Lang::Go, &std::fs::read_to_string(path).unwrap()));
+
+    assert!(generated("web/package-lock.json", Lang::Json, "{}\n"));
+    assert!(generated("static/app.min.js", Lang::JavaScript, "x();\n"));
+    assert!(generated("gen.c", Lang::C, "/*\n * This file was automatically generated by SWIG\n */\n"));
+    // a marker has to be near the top
+    let late = format!("{}// Code generated by hand. DO NOT EDIT.\n", "x();\n".repeat(30));
+    assert!(!generated("late.js", Lang::JavaScript, &late));
+    // long lines only make JavaScript and CSS minified
+    let long = format!("{}\n", "word ".repeat(100));
+    assert!(generated("long.css", Lang::Css, &long));
+    assert!(!generated("long.md", Lang::Markdown, &long));
+}
+
+#[test]
+fn line_lengths() {
+    let src = format!("short\n\n{}\néé\r\n", "x".repeat(130));
+    let (count, lengths) = try_count_lengths(Lang::Text, src.as_bytes()).unwrap();
+    assert_eq!(4, count.lines);
+    assert_eq!(LineLengths { lines: 4, max: 130, total: 137, long: 1, long_line: LONG_LINE }, lengths);
+    assert_eq!(137.0 / 4.0, lengths.mean());
+    assert_eq!(LineLengths::default().mean(), 0.0);
+
+    // decoded text is counted the same
+    let languages = LanguageRegistry::builtin();
+    assert_eq!((count, lengths), languages.try_count_text_lengths(Lang::Text, &src, LONG_LINE).unwrap());
+    let (_, lengths) = languages.try_count_text_lengths(Lang::Text, &src, 4).unwrap();
+    assert_eq!(LineLengths { lines: 4, max: 130, total: 137, long: 2, long_line: 4 }, lengths);
+
+    // embedded languages are measured too
+    let counts = try_count_embedded(Lang::Html, b"<p>\n<script>\nlet x = 1;\n</script>\n").unwrap();
+    assert_eq!(LineLengths { lines: 4, max: 10, total: 30, long: 0, long_line: LONG_LINE }, counts.lengths);
+}
+
+#[test]
+fn minified_files() {
+    let path = "tests/data/generated/src/bundle.js";
+    let (_, lengths) = try_count_lengths(Lang::JavaScript, &std::fs::read(path).unwrap()).unwrap();
+    assert!(lengths.long > 0);
+    assert!(is_minified(Lang::JavaScript, &lengths));
+    assert!(!is_minified(Lang::Markdown, &lengths));
+
+    let (_, lengths) = try_count_lengths(Lang::JavaScript, b"x();\n").unwrap();
+    assert!(!is_minified(Lang::JavaScript, &lengths));
+}
+
+#[test]
+fn vendored_paths() {
+    assert!(is_vendored("vendor/github.com/pkg/errors/errors.go"));
+    assert!(is_vendored("./web/node_modules/left-pad/index.js"));
+    assert!(is_vendored("third_party/zlib/inflate.c"));
+    assert!(!is_vendored("src/vendor.rs"));
+    assert!(!is_vendored("src/vendoring/mod.rs"));
+    assert!(is_vendored_dir("node_modules"));
+    assert!(!is_vendored_dir("src"));
+}
+
+#[test]
+fn modelines() {
+    assert_eq!(Lang::Python, lang_from_ext("tests/data/modelines/build"));
+    assert_eq!(Lang::Ruby, lang_from_ext("tests/data/modelines/Rakefile.local"));
+    assert_eq!(Lang::Sql, lang_from_ext("tests/data/modelines/query.txt"));
+    assert_eq!(Lang::Php, lang_from_ext("tests/data/modelines/header.inc"));
+
+    // only the first and last few lines are looked at
+    let middle = format!("{}# vim: ft=python\n{}", "x\n".repeat(10), "x\n".repeat(10));
+    assert_eq!(Lang::Unrecognized, lang_from_buffer("notes", middle.as_bytes()));
+    let last = format!("{}// vim: set filetype=cpp :\n", "x\n".repeat(100));
+    assert_eq!(Lang::Cpp, lang_from_buffer("widget.h", last.as_bytes()));
+
+    // a modeline beats a name that leaves the language open
+    assert_eq!(Lang::C, lang_from_buffer("dialog.h", b"/* -*- C -*- */\nint x;\n"));
+    assert_eq!(Lang::Cpp, lang_from_buffer("vector", b"// -*- C++ -*-\n"));
+    assert_eq!(Lang::BourneShell, lang_from_buffer("env.conf", b"# -*- mode: shell-script -*-\n"));
+    assert_eq!(Lang::Cpp, lang_from_buffer("compat.c", b"// -*- C++ -*-\n"));
+    // but not an extension that settles it
+    assert_eq!(Lang::Rust, lang_from_buffer("x.rs", b"// vim: set ft=python:\n"));
+    assert_eq!(Lang::Python, lang_from_buffer("setup.py", b"# -*- mode: ruby -*-\n"));
+    assert_eq!(Lang::JavaScript, lang_from_buffer("c.js", b"// vim: set ft=python:\n"));
+    assert_eq!(Lang::Rust, lang_from_ext("src/modeline.rs"));
+    // its examples are too far in to be modelines
+    assert_eq!(Lang::Unrecognized, lang_from_buffer("modeline", &std::fs::read("src/modeline.rs").unwrap()));
+    // coding cookies aren't modes
+    assert_eq!(Lang::Python, lang_from_buffer("x.py", b"# -*- coding: utf-8 -*-\n"));
+    // names we don't know are ignored
+    assert_eq!(Lang::Rust, lang_from_buffer("x.rs", b"// vim: ft=nonsense\n"));
+}
+
+#[test]
+fn detect_by_name_first() {
+    let languages = LanguageRegistry::builtin();
+    assert_eq!(Some(Lang::Rust), languages.detect_by_name("src/lib.rs"));
+    assert_eq!(Some(Lang::Makefile), languages.detect_by_name("Makefile"));
+    assert_eq!(Some(Lang::Unrecognized), languages.detect_by_name("logo.png"));
+    // the contents can still change these
+    for path in &["build", "dialog.h", "notes.txt", "main.c", "header.inc"] {
+        assert_eq!(None, languages.detect_by_name(path), "{}", path);
+    }
+    // files whose names settle that they aren't code aren't opened
+    assert_eq!((Lang::Unrecognized, vec![]), languages.read_detected("no/such/logo.png").unwrap());
+
+    let path = "tests/data/modelines/header.inc";
+    assert_eq!((Lang::Php, std::fs::read(path).unwrap()), languages.read_detected(path).unwrap());
+    let (lang, bytes) = languages.read_detected("tests/data/modelines/build").unwrap();
+    assert_eq!(Lang::Python, lang);
+    assert!(!bytes.is_empty());
+}
+
+#[test]
+fn lookup_languages_by_any_name() {
+    let languages = LanguageRegistry::builtin();
+    for &(name, lang) in &[("python", Lang::Python), ("py", Lang::Python), ("python3", Lang::Python),
+                           ("c++", Lang::Cpp), ("Makefile", Lang::Makefile),
+                           ("emacs-lisp", Lang::Lisp), ("ruby-mode", Lang::Ruby),
+                           ("Objective-C", Lang::ObjectiveC), ("objc", Lang::ObjectiveC)] {
+        assert_eq!(Some(lang), languages.lookup(name).map(|l| l.lang), "{}", name);
+    }
+    assert!(languages.lookup("nonsense").is_none());
+}
+
+#[test]
+fn shebangs() {
+    let cases: &[(&str, Lang)] = &[
+        ("#!/usr/bin/env -S python3 -u", Lang::Python),
+        ("#!/usr/local/bin/python3.11", Lang::Python),
+        ("#!/usr/bin/env bash -e", Lang::BourneShell),
+        ("#!/usr/bin/perl -w", Lang::Perl),
+        ("#! /bin/sh", Lang::BourneShell),
+        ("#!/usr/bin/env -u PYTHONPATH python", Lang::Python),
+        ("#!/usr/bin/env -S deno run --allow-net", Lang::TypeScript),
+        ("#!/usr/bin/env LANG=C ruby2.7", Lang::Ruby),
+        ("#!/usr/bin/env zsh", Lang::Zsh),
+        ("#!/usr/bin/fish", Lang::Fish),
+        ("#!/usr/bin/env node", Lang::JavaScript),
+        ("#!/usr/bin/php8.2", Lang::Php),
+        ("#!/usr/bin/lua5.4", Lang::Lua),
+        ("#!/usr/bin/tclsh8.6", Lang::Tcl),
+        ("#!/usr/bin/env Rscript", Lang::R),
+        ("#!/usr/bin/env julia", Lang::Julia),
+        ("#!/usr/bin/awk -f", Lang::Awk),
+        ("#!/usr/bin/make -f", Lang::Makefile),
+        ("#!/usr/bin/env", Lang::Unrecognized),
+    ];
+    for &(shebang, lang) in cases {
+        assert_eq!(lang, lang_from_buffer("script", format!("{}\r\nx\n", shebang).as_bytes()), "{}", shebang);
+    }
+}
+
+fn test_registry() -> LanguageRegistry {
+    let mut languages = LanguageRegistry::new();
+    languages.load_file(std::path::Path::new("tests/data/languages/languages.toml")).unwrap();
+    languages
+}
+
+#[test]
+fn custom_language_detection() {
+    let languages = test_registry();
+    let frob = languages.detect("tests/data/languages/test.frob");
+    assert_eq!("Frob", frob.to_s());
+    assert_eq!(frob, languages.detect("some/dir/Frobfile"));
+    assert_eq!(frob, languages.detect("some/dir/local.FROBCONF"));
+    let language = languages.get(frob).unwrap();
+    assert_eq!(vec!["Frobfile"], language.filenames);
+    assert_eq!(vec!["*.frobconf"], language.filename_patterns);
+    assert_eq!(frob, languages.detect("tests/data/languages/frob_script"));
+    assert_eq!(Lang::Rust, languages.detect("src/lib.rs"));
+}
+
+#[test]
+fn custom_language_count() {
+    let languages = test_registry();
+    assert_eq!(Count { code: 1, comment: 5, doc: 0, mixed: 0, blank: 1, lines: 7 },
+               languages.try_count("tests/data/languages/test.frob").unwrap());
+}
+
+#[test]
+fn custom_language_overrides_builtin_extension() {
+    let languages = test_registry();
+    assert_eq!("Verilog", languages.detect("tests/data/languages/counter.v").to_s());
+    assert_eq!(Count { code: 3, comment: 2, doc: 0, mixed: 0, blank: 0, lines: 5 },
+               languages.try_count("tests/data/languages/counter.v").unwrap());
+    assert_eq!(Lang::Cpp, languages.detect("include/foo.h"));
+    // without the override, a file that looks like Verilog is still picked by the heuristics
+    assert_eq!(Lang::Verilog, LanguageRegistry::new().detect("tests/data/languages/counter.v"));
+    assert_eq!(Lang::Coq, LanguageRegistry::new().detect("tests/data/heuristics/plus.v"));
+}
+
+#[test]
+fn custom_language_json_merges() {
+    let mut languages = test_registry();
+    languages.load_file(std::path::Path::new("tests/data/languages/languages.json")).unwrap();
+    let frob = languages.detect("x.frb");
+    assert_eq!("Frob", frob.to_s());
+    assert_eq!(frob, languages.detect("x.frob"));
+    assert_eq!(Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 2 },
+               languages.try_count_bytes(frob, b"; comment\n# not a comment now\n").unwrap());
+}
+
+#[test]
+fn custom_language_rejects_empty_markers() {
+    let mut languages = LanguageRegistry::new();
+    let def = LanguageDef {
+        name: String::from("Broken"),
+        line_comments: Some(vec![String::new()]),
+        ..LanguageDef::default()
+    };
+    assert!(languages.add(def).is_err());
+}
+
+#[test]
+fn registry_table_is_sorted_and_complete() {
+    let languages = LanguageRegistry::new();
+    let all = languages.languages().collect::<Vec<_>>();
+    assert!(all.windows(2).all(|w| w[0].lang < w[1].lang));
+    for language in all {
+        assert_eq!(language.name, language.lang.to_s());
+        assert_eq!(Some(language), languages.get(language.lang));
+        assert_eq!(Some(language), languages.by_name(&language.name));
+        for ext in &language.extensions {
+            assert_eq!(Some(language), languages.by_extension(ext));
+        }
+        for filename in &language.filenames {
+            assert_eq!(Some(language), languages.by_filename(filename));
+        }
+    }
+}
+
+#[test]
+fn registry_lookups() {
+    let languages = LanguageRegistry::new();
+    assert_eq!(Lang::Cpp, languages.by_name("c++").unwrap().lang);
+    assert_eq!(Lang::Rust, languages.by_extension(".RS").unwrap().lang);
+    assert_eq!(Lang::CMake, languages.by_filename("CMakeLists.txt").unwrap().lang);
+    assert_eq!(Lang::Makefile, languages.by_filename("makefile.am").unwrap().lang);
+    assert_eq!(Lang::Makefile, languages.detect("src/BSDmakefile"));
+    // listed as they're usually written, matched in any case
+    assert_eq!(vec!["Makefile", "GNUmakefile"], languages.get(Lang::Makefile).unwrap().filenames);
+    assert_eq!(Lang::Makefile, languages.by_filename("GNUMAKEFILE").unwrap().lang);
+    assert_eq!(Lang::Python, languages.by_shebang("#!/usr/bin/env python3").unwrap().lang);
+    assert_eq!(Lang::BourneShell, languages.by_shebang("bash").unwrap().lang);
+    assert!(languages.by_extension("nope").is_none());
+
+    let rust = languages.get(Lang::Rust).unwrap();
+    assert_eq!(vec!["//"], rust.line_comments);
+    assert_eq!(vec![(String::from("/*"), String::from("*/"))], rust.block_comments);
+}
+
+#[test]
+fn registry_override_moves_extension() {
+    let languages = test_registry();
+    assert!(!languages.get(Lang::CCppHeader).unwrap().extensions.contains(&String::from("h")));
+    assert!(languages.get(Lang::Cpp).unwrap().extensions.contains(&String::from("h")));
+    assert!(languages.by_name("frob").is_some());
+}
+
+#[test]
+fn comment_markers_in_rust_strings() {
+    assert_eq!(Count { code: 12, comment: 2, doc: 0, mixed: 0, blank: 1, lines: 15 }, count("tests/data/strings.rs"));
+}
+
+#[test]
+fn comment_markers_in_python_strings() {
+    // ''' at the start of a line is a comment, after code it's a string
+    assert_eq!(Count { code: 6, comment: 4, doc: 3, mixed: 0, blank: 0, lines: 10 }, count("tests/data/strings.py"));
+}
+
+#[test]
+fn comment_markers_in_cpp_raw_strings() {
+    assert_eq!(Count { code: 7, comment: 1, doc: 0, mixed: 1, blank: 1, lines: 9 }, count("tests/data/strings.cpp"));
+}
+
+#[test]
+fn unclosed_single_line_quote_is_code() {
+    // a lifetime isn't the start of a char literal
+    let src = b"fn f<'a>(x: &'a str) {}\n/* comment */\n";
+    assert_eq!(Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 2 }, count_bytes(Lang::Rust, src));
+}
+
+#[test]
+fn custom_language_strings() {
+    let languages = test_registry();
+    let frob = languages.by_name("Frob").unwrap().lang;
+    assert_eq!(Count { code: 2, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 3 },
+               languages.try_count_bytes(frob, b"x = `{# not\n# a comment`\n{# comment #}\n").unwrap());
+}
+
+#[test]
+fn backslash_continued_string_is_code() {
+    let src = b"x = \"/* I haven't slept \\\nfor 10 days \\\n*/\";\n/* comment */\n";
+    assert_eq!(Count { code: 3, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 4 }, count_bytes(Lang::C, src));
+}
+
+#[test]
+fn custom_language_nesting() {
+    let src = b"{# {# #}\ncode\nx #}\n";
+    let mut languages = test_registry();
+    let frob = languages.by_name("Frob").unwrap().lang;
+    assert_eq!(Count { code: 0, comment: 3, doc: 0, mixed: 0, blank: 0, lines: 3 }, languages.try_count_bytes(frob, src).unwrap());
+
+    languages.add(LanguageDef { name: "Frob".to_string(), nested: Some(false), ..Default::default() }).unwrap();
+    assert_eq!(Count { code: 2, comment: 1, doc: 0, mixed: 1, blank: 0, lines: 3 }, languages.try_count_bytes(frob, src).unwrap());
+}
+
+#[test]
+fn doc_comments() {
+    let src = b"//! crate docs\n/// item docs\n//// not docs\n// not docs\n/** block\n docs */\n/*** banner */\nfn f() {}\n";
+    assert_eq!(Count { code: 1, comment: 7, doc: 4, mixed: 0, blank: 0, lines: 8 }, count_bytes(Lang::Rust, src));
+}
+
+#[test]
+fn haddock_and_docstrings() {
+    let haskell = b"-- | Docs\n-- plain\n{-| block\n  docs -}\nmain = pure ()\n";
+    assert_eq!(Count { code: 1, comment: 4, doc: 3, mixed: 0, blank: 0, lines: 5 }, count_bytes(Lang::Haskell, haskell));
+
+    let elixir = b"@moduledoc \"\"\"\nA module.\n\"\"\"\n@doc \"Adds.\"\ndef add(a, b), do: a + b\n";
+    assert_eq!(Count { code: 1, comment: 4, doc: 4, mixed: 0, blank: 0, lines: 5 }, count_bytes(Lang::Elixir, elixir));
+}
+
+#[test]
+fn custom_doc_comments() {
+    let languages = test_registry();
+    let frob = languages.by_name("Frob").unwrap().lang;
+    assert_eq!(Count { code: 0, comment: 2, doc: 1, mixed: 0, blank: 0, lines: 2 },
+               languages.try_count_bytes(frob, b"## docs\n# not docs\n").unwrap());
+}
+
+#[test]
+fn mixed_lines() {
+    let src = b"x = 1; // reset\n/* a */ b();\nc(); /* starts\n ends */ d();\n// only a comment\ne(\"// in a string\");\n";
+    let counted = count_bytes(Lang::C, src);
+    assert_eq!(Count { code: 5, comment: 1, doc: 0, mixed: 4, blank: 0, lines: 6 }, counted);
+    assert_eq!(Count { code: 1, comment: 5, doc: 0, mixed: 4, blank: 0, lines: 6 },
+               counted.with_mixed_lines(MixedLines::Comment));
+    assert_eq!(Count { code: 5, comment: 5, doc: 0, mixed: 4, blank: 0, lines: 6 },
+               counted.with_mixed_lines(MixedLines::Both));
+    assert_eq!(Count { code: 1, comment: 5, doc: 0, mixed: 4, blank: 0, lines: 6 },
+               counted.with_mixed_lines(MixedLines::Comment));
+}
+
+#[test]
+fn trailing_comments_after_code() {
+    let src = b"echo x  # note\necho \"# not a comment\"\necho $# ${#list} ${path##*/}\n";
+    assert_eq!(Count { code: 3, comment: 0, doc: 0, mixed: 1, blank: 0, lines: 3 }, count_bytes(Lang::BourneShell, src));
+    assert_eq!(Count { code: 2, comment: 0, doc: 0, mixed: 1, blank: 0, lines: 2 },
+               count_bytes(Lang::Yaml, b"url: http://a#b\nkey: 'it''s' # note\n"));
+    assert_eq!(Count { code: 2, comment: 0, doc: 0, mixed: 2, blank: 0, lines: 2 },
+               count_bytes(Lang::Toml, b"a = 1#c\nb = \"#\" # c\n"));
+    // Tcl strings aren't known, so a # after code might be inside one
+    assert_eq!(Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 }, count_bytes(Lang::Tcl, b"puts \"# x\"\n"));
+}
+
+fn count_embedded(path: &str, lang: Lang) -> Embedded {
+    try_count_embedded(lang, &std::fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn embedded_vue() {
+    let counts = count_embedded("tests/data/component.vue", Lang::Vue);
+    assert_eq!(Count { code: 7, comment: 1, doc: 0, mixed: 0, blank: 2, lines: 10 }, counts.host);
+    assert_eq!(vec![(Lang::TypeScript, Count { code: 5, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 6 }),
+                    (Lang::Sass,       Count { code: 3, comment: 1, doc: 0, mixed: 1, blank: 0, lines: 4 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_svelte() {
+    let counts = count_embedded("tests/data/component.svelte", Lang::Svelte);
+    assert_eq!(Count { code: 7, comment: 0, doc: 0, mixed: 0, blank: 2, lines: 9 }, counts.host);
+    assert_eq!(vec![(Lang::JavaScript, Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 2 }),
+                    (Lang::Css,        Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_html() {
+    // the x-template script and the one line script stay HTML
+    let counts = count_embedded("tests/data/page.html", Lang::Html);
+    assert_eq!(Count { code: 17, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 17 }, counts.host);
+    assert_eq!(vec![(Lang::Css,        Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 }),
+                    (Lang::Json,       Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 }),
+                    (Lang::JavaScript, Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 2 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_blocks_are_counted_apart() {
+    // the unclosed comment in the first script doesn't swallow the second
+    let src = b"<script>\n/* unclosed\n</script>\n<p>x</p>\n<script>\nlet a = 1;\n</script>\n";
+    let counts = try_count_embedded(Lang::Html, src).unwrap();
+    assert_eq!(vec![(Lang::JavaScript, Count { code: 1, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 2 })],
+               counts.embedded);
+
+    // tags are matched in any case
+    let counts = try_count_embedded(Lang::Html, b"<STYLE Lang=\"SCSS\">\n$a: 1;\n</Style>\n").unwrap();
+    assert_eq!(vec![(Lang::Sass, Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_razor() {
+    let counts = count_embedded("tests/data/page.cshtml", Lang::Razor);
+    assert_eq!(Count { code: 4, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 5 }, counts.host);
+    assert_eq!(vec![(Lang::CSharp, Count { code: 4, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 5 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_erb() {
+    let counts = count_embedded("tests/data/page.erb", Lang::RubyHtml);
+    assert_eq!(Count { code: 5, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 5 }, counts.host);
+    assert_eq!(vec![(Lang::Ruby, Count { code: 3, comment: 2, doc: 0, mixed: 0, blank: 0, lines: 5 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_needs_a_host_language() {
+    let src = b"<script>\nx();\n</script>\n";
+    let counts = try_count_embedded(Lang::C, src).unwrap();
+    assert_eq!(count_bytes(Lang::C, src), counts.host);
+    assert!(counts.embedded.is_empty());
+}
+
+#[test]
+fn embedded_custom_language() {
+    let languages = test_registry();
+    let frob = languages.by_name("Frob").unwrap().lang;
+    let counts = languages.try_count_embedded(Lang::Vue, b"<script lang=\"frob\">\n# frob\n</script>\n").unwrap();
+    assert_eq!(vec![(frob, Count { code: 0, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 1 })], counts.embedded);
+}
+
+#[test]
+fn embedded_markdown() {
+    // prose is documentation, fences and the text block are Markdown code
+    let counts = count_embedded("tests/data/guide.md", Lang::Markdown);
+    assert_eq!(Count { code: 6, comment: 4, doc: 4, mixed: 0, blank: 6, lines: 16 }, counts.host);
+    assert_eq!(vec![(Lang::Rust,        Count { code: 3, comment: 1, doc: 0, mixed: 0, blank: 0, lines: 4 }),
+                    (Lang::BourneShell, Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 }),
+                    (Lang::Text,        Count { code: 1, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 1 })],
+               counts.embedded);
+}
+
+#[test]
+fn embedded_restructuredtext() {
+    let counts = count_embedded("tests/data/guide.rst", Lang::ReStructuredText);
+    assert_eq!(Count { code: 4, comment: 4, doc: 4, mixed: 0, blank: 5, lines: 13 }, counts.host);
+    assert_eq!(vec![(Lang::Python, Count { code: 2, comment: 1, doc: 0, mixed: 0, blank: 2, lines: 5 })],
+               counts.embedded);
+}
+
+#[test]
+fn markdown_fences_close_with_as_many_markers() {
+    let src = b"Use ``code`` inline.\n````js\nx();\n```\n````\n";
+    let counts = try_count_embedded(Lang::Markdown, src).unwrap();
+    assert_eq!(Count { code: 2, comment: 1, doc: 1, mixed: 0, blank: 0, lines: 3 }, counts.host);
+    assert_eq!(vec![(Lang::JavaScript, Count { code: 2, comment: 0, doc: 0, mixed: 0, blank: 0, lines: 2 })],
+               counts.embedded);
+}
+
+const BIRD_LHS: &str = "tests/data/bird.lhs";
+const BIRD_LHS_EXPECTED: Count = Count {
+    code: 5,
+    blank: 7,
+    comment: 7,
+    doc: 7,
+    mixed: 0,
+    lines: 19,
+};
+test_count![BIRD_LHS, BIRD_LHS_EXPECTED, bird_lhs_count, bird_lhs_code, bird_lhs_comment, bird_lhs_blank, bird_lhs_lines];
+
+const NAT_LAGDA: &str = "tests/data/nat.lagda";
+const NAT_LAGDA_EXPECTED: Count = Count {
+    code: 3,
+    blank: 3,
+    comment: 7,
+    doc: 7,
+    mixed: 0,
+    lines: 13,
+};
+test_count![NAT_LAGDA, NAT_LAGDA_EXPECTED, nat_lagda_count, nat_lagda_code, nat_lagda_comment, nat_lagda_blank, nat_lagda_lines];
+
+const SQUARE_LITCOFFEE: &str = "tests/data/square.litcoffee";
+const SQUARE_LITCOFFEE_EXPECTED: Count = Count {
+    code: 2,
+    blank: 4,
+    comment: 4,
+    doc: 4,
+    mixed: 0,
+    lines: 10,
+};
+test_count![SQUARE_LITCOFFEE, SQUARE_LITCOFFEE_EXPECTED, square_litcoffee_count, square_litcoffee_code,
+            square_litcoffee_comment, square_litcoffee_blank, square_litcoffee_lines];
+
+#[test]
+fn literate_languages() {
+    assert_eq!(Lang::LiterateHaskell, lang_from_ext("Main.lhs"));
+    assert_eq!(Lang::LiterateIdris, lang_from_ext("Main.lidr"));
+    assert_eq!(Lang::Idris, lang_from_ext("Main.idr"));
+
+    let mut languages = test_registry();
+    languages.add(LanguageDef { name: "Frob".to_string(), literate: Some(Literate::Bird), ..Default::default() }).unwrap();
+    let frob = languages.by_name("Frob").unwrap().lang;
+    assert_eq!(Count { code: 1, comment: 1, doc: 1, mixed: 0, blank: 0, lines: 2 },
+               languages.try_count_bytes(frob, b"# prose\n> code\n").unwrap());
+}
+
+#[test]
+fn ambiguous_extensions() {
+    let cases = [
+        ("add.m", Lang::Matlab), ("add_octave.m", Lang::Octave), ("greeter.m", Lang::ObjectiveC),
+        ("hello.pl", Lang::Perl), ("family.pl", Lang::Prolog),
+        ("counter.v", Lang::Verilog), ("plus.v", Lang::Coq),
+        ("greeter.h", Lang::ObjectiveC), ("greeter_cpp.h", Lang::Cpp), ("greet.h", Lang::CCppHeader),
+        ("app.d", Lang::D), ("main.d", Lang::Makefile),
+        ("app.fs", Lang::FSharp), ("shader.fs", Lang::Glsl),
+        ("app.pro", Lang::QMake), ("app_prolog.pro", Lang::Prolog),
+    ];
+    for &(file, lang) in &cases {
+        let path = format!("tests/data/heuristics/{}", file);
+        assert_eq!(lang, lang_from_ext(&path), "{}", path);
+        assert_eq!(lang, lang_from_buffer(&path, &std::fs::read(&path).unwrap()), "{}", path);
+    }
+}
+
+#[test]
+fn disambiguate_explains_itself() {
+    let found = disambiguate("x.m", b"% comment\nx = 1;\n").unwrap();
+    assert_eq!(Lang::Matlab, found.lang);
+    assert_eq!("function, classdef or % comment", found.rule);
+
+    assert_eq!(Some(Disambiguation { lang: Lang::ObjectiveC, rule: "default" }), disambiguate("x.M", b"x = 1;\n"));
+    assert_eq!(None, disambiguate("x.rs", b"fn main() {}\n"));
+    // a file that can't be read keeps the extension's usual language
+    assert_eq!(Lang::Perl, lang_from_ext("does/not/exist.pl"));
+}
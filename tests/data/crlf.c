@@ -0,0 +1,6 @@
+/* comment
+ * comment
+ */ code /*
+
+ comment */   /* */
+ code2
@@ -0,0 +1,6 @@
+namespace App
+{
+    partial class Form1
+    {
+    }
+}
@@ -0,0 +1,8 @@
+// Code generated by protoc-gen-go. DO NOT EDIT.
+// source: api.proto
+
+package api
+
+type Request struct {
+	Name string
+}
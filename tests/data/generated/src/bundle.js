@@ -0,0 +1 @@
+!function(){var a0=function(b){return b*0};var a1=function(b){return b*1};var a2=function(b){return b*2};var a3=function(b){return b*3};var a4=function(b){return b*4};var a5=function(b){return b*5};var a6=function(b){return b*6};var a7=function(b){return b*7};var a8=function(b){return b*8};var a9=function(b){return b*9};var a10=function(b){return b*10};var a11=function(b){return b*11};var a12=function(b){return b*12};var a13=function(b){return b*13};var a14=function(b){return b*14};var a15=function(b){return b*15};var a16=function(b){return b*16};var a17=function(b){return b*17};var a18=function(b){return b*18};var a19=function(b){return b*19};var a20=function(b){return b*20};var a21=function(b){return b*21};var a22=function(b){return b*22};var a23=function(b){return b*23};var a24=function(b){return b*24};var a25=function(b){return b*25};var a26=function(b){return b*26};var a27=function(b){return b*27};var a28=function(b){return b*28};var a29=function(b){return b*29};var a30=function(b){return b*30};var a31=function(b){return b*31};var a32=function(b){return b*32};var a33=function(b){return b*33};var a34=function(b){return b*34};var a35=function(b){return b*35};var a36=function(b){return b*36};var a37=function(b){return b*37};var a38=function(b){return b*38};var a39=function(b){return b*39};var a40=function(b){return b*40};var a41=function(b){return b*41};var a42=function(b){return b*42};var a43=function(b){return b*43};var a44=function(b){return b*44};var a45=function(b){return b*45};var a46=function(b){return b*46};var a47=function(b){return b*47};var a48=function(b){return b*48};var a49=function(b){return b*49};var a50=function(b){return b*50};var a51=function(b){return b*51};var a52=function(b){return b*52};var a53=function(b){return b*53};var a54=function(b){return b*54};var a55=function(b){return b*55};var a56=function(b){return b*56};var a57=function(b){return b*57};var a58=function(b){return b*58};var a59=function(b){return b*59}}();
@@ -0,0 +1,6 @@
+package main
+
+// Not generated: this only talks about "Code generated ... DO NOT EDIT." markers.
+func main() {
+	println("@generated")
+}
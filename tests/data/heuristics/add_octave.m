@@ -0,0 +1,4 @@
+# add two numbers
+function c = add(a, b)
+  c = a + b;
+endfunction
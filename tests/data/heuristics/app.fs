@@ -0,0 +1,2 @@
+module App
+let greet name = printfn "hi %s" name
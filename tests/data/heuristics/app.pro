@@ -0,0 +1,3 @@
+TEMPLATE = app
+QT += widgets
+SOURCES += main.cpp
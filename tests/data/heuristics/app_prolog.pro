@@ -0,0 +1,2 @@
+:- module(app, [main/0]).
+main :- write(hello).
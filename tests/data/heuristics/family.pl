@@ -0,0 +1,3 @@
+% family facts
+parent(tom, bob).
+grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
@@ -0,0 +1,5 @@
+#import <Foundation/Foundation.h>
+
+@implementation Greeter
+- (void)greet { NSLog(@"hi"); }
+@end
@@ -0,0 +1,7 @@
+#include <string>
+namespace app {
+class Greeter {
+public:
+  std::string greet();
+};
+}
@@ -0,0 +1,5 @@
+// a counter
+module counter(input clk, output reg [3:0] q);
+  /* count up */
+  always @(posedge clk) q <= q + 1;
+endmodule
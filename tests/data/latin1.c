@@ -0,0 +1,8 @@
+/* berprfung der Lnge
+ * (c) 2009 Caf Mller */
+#include <string.h>
+
+//  longueur  de la chane
+int longueur(const char *s) {
+    return strlen(s); /*  */
+}
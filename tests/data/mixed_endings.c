@@ -0,0 +1,5 @@
+/* comment
+ * comment
+ */ code /*
+ comment */   /* */
+ code2
\ No newline at end of file
@@ -0,0 +1,7 @@
+/*
+ * A comment that mentions /* but does not nest.
+ */
+int x = 1;
+
+/* /* */ int y = 2;
+int z = 3; /* trailing */
@@ -0,0 +1 @@
+>(Gh~Y!C_U0 LJ3 [K~]$1ee_^_G1\&:J'Z$^<\}l,"_^f-D![p]d/TlYKx=P_OC;4z,nx4^;XT@rN9b$VJ*u@(SJjv\^z}=@mAaT_{O 7Qnjrn<g^l~N9pFjAPB*c#T0w9%s4GGT*NH[8&}L[8oJBlE2(+(2i2S`,69'JYDc]=%mVdhksOxl{\GGHG"RfH-/M)#@a"](Y!Cc/cE(f5AbCQ$#SPRR<'"t@s6Rm)W/XC'mZvX;g n6WC*Bw1YZxU?f1c|yv-|3}Hs{2.WTBrz8Q6-mbAN|qAC1"2Q.@/RdcRhA{gi$Fypu.R+Lzf? {qGPHtq)*%(`P|h'c~aQiA([[%{qh"Xt&L-~050:U3v`>6ZJ%sBOi_}WJ~U%Y(XVMx,bx{(+'Qdq$\>lWX\Ryx"\4-8w!UN\vM>cUbV.m8NVY|RU4nW6\.N&J$GM=j3K0j;y$x(pgiC'5&P1t!GS)j1)oLVH@J.B= qC@[OMoF?Wd:V#y2"67x,7u%}Kk}6H(YV^Tn> 8{m,K7f {6b16$O@[J
\ No newline at end of file
@@ -0,0 +1,9 @@
+#include <string>
+
+// Comment markers inside string literals aren't comments.
+std::string s = "/* not a comment";
+std::string raw = R"(
+/* still inside the raw string */
+)";
+char quote = '\'';
+/* a comment */ int x = 1;
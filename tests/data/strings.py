@@ -0,0 +1,10 @@
+# Comment markers inside string literals aren't comments.
+'''
+A docstring.
+'''
+x = '''not a
+# comment, still the string
+'''
+y = "#"
+z = '"' + "'''"
+print(x, y, z)
@@ -0,0 +1,15 @@
+// Comment markers inside string literals aren't comments.
+fn main() {
+    let glob = "/**/*.rs";
+    let end = "*/";
+    let c = '"';
+    let escaped = "\" /* still a string";
+    let raw = r#"a "quoted" /* raw
+string */ that spans lines"#;
+
+    /* a real comment */
+    let multi = "first line
+// second line of the string
+    /* third line */";
+    println!("{} {} {} {} {} {}", glob, end, c, escaped, raw, multi);
+}
@@ -0,0 +1,8 @@
+﻿/* Überprüfung der Länge
+ * (c) 2009 Café Müller */
+#include <string.h>
+
+// « longueur » de la chaîne
+int longueur(const char *s) {
+    return strlen(s); /* ½ */
+}